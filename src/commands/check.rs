@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::helpers::write_deletion;
+use crate::commands::rendering::{prefixed_uri, write_table};
+use crate::commands::RunnableCommand;
+use crate::operations::delete::delete_twins;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::integrity::{check_integrity, IntegrityIssue};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct CheckArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// The maximum number of twins described at the same time
+    #[structopt(long, default_value = "8")]
+    pub concurrency: usize,
+    /// Print how to fix every issue
+    #[structopt(long)]
+    pub plan: bool,
+    /// Only print the DIDs of the orphan twins, one per line, e.g. for `export --file -`
+    #[structopt(long, conflicts_with_all = &["plan", "delete-orphans"])]
+    pub orphans: bool,
+    /// List the orphan twins to delete, the twins whose model is gone. Only deleted with `--yes`
+    #[structopt(long)]
+    pub delete_orphans: bool,
+    /// Delete the orphan twins listed by `--delete-orphans`
+    #[structopt(long, requires = "delete-orphans")]
+    pub yes: bool,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+fn describe_issue(issue: &IntegrityIssue) -> (String, String) {
+    match issue {
+        IntegrityIssue::OrphanTwin {
+            twin_did,
+            model_did,
+        } => (twin_did.clone(), format!("model {} not found", model_did)),
+        IntegrityIssue::UnusedModel { model_did } => (
+            model_did.clone(),
+            "no twin created from the model".to_string(),
+        ),
+        IntegrityIssue::MissingProperty {
+            twin_did,
+            model_did,
+            key,
+        } => (
+            twin_did.clone(),
+            format!(
+                "no {} property, defined on model {}",
+                prefixed_uri(key),
+                model_did
+            ),
+        ),
+        IntegrityIssue::MissingFeed {
+            twin_did,
+            model_did,
+            feed_id,
+        } => (
+            twin_did.clone(),
+            format!("no {} feed, defined on model {}", feed_id, model_did),
+        ),
+        IntegrityIssue::DuplicateLabel { label, twins_dids } => (
+            twins_dids.join(", "),
+            format!("{} twins labelled {:?}", twins_dids.len(), label),
+        ),
+    }
+}
+
+fn fix(issue: &IntegrityIssue) -> String {
+    match issue {
+        IntegrityIssue::OrphanTwin { twin_did, .. } => {
+            format!(
+                "delete twin {}, e.g. with `check --delete-orphans --yes`",
+                twin_did
+            )
+        }
+        IntegrityIssue::UnusedModel { model_did } => format!(
+            "delete model {} or create twins from it with `model instantiate`",
+            model_did
+        ),
+        IntegrityIssue::MissingProperty {
+            twin_did,
+            model_did,
+            key,
+        } => format!(
+            "set {} on twin {} to the value of model {}",
+            prefixed_uri(key),
+            twin_did,
+            model_did
+        ),
+        IntegrityIssue::MissingFeed {
+            twin_did,
+            model_did,
+            feed_id,
+        } => format!(
+            "add feed {} of model {} to twin {}",
+            feed_id, model_did, twin_did
+        ),
+        IntegrityIssue::DuplicateLabel { twins_dids, .. } => {
+            format!(
+                "relabel {} of twins {}",
+                twins_dids.len() - 1,
+                twins_dids.join(", ")
+            )
+        }
+    }
+}
+
+pub struct Check<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: CheckArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> Check<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: CheckArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(stdout: &'a mut W, opts: CheckArgs, host_api: Arc<dyn HostApi>) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for Check<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let issues = check_integrity(self.host_api.clone(), self.opts.concurrency).await?;
+
+        let orphans_dids = issues
+            .iter()
+            .filter_map(|issue| match issue {
+                IntegrityIssue::OrphanTwin { twin_did, .. } => Some(twin_did.clone()),
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+
+        if self.opts.orphans {
+            for twin_did in &orphans_dids {
+                writeln!(self.stdout, "{}", twin_did)?;
+            }
+
+            self.stdout.flush()?;
+            return Ok(());
+        }
+
+        let rows = issues
+            .iter()
+            .map(|issue| {
+                let (twins, detail) = describe_issue(issue);
+                vec![issue.kind().to_string(), twins, detail]
+            })
+            .collect::<Vec<Vec<String>>>();
+
+        if !rows.is_empty() {
+            write_table(self.stdout, "", &["Issue", "Twins", "Detail"], &rows)?;
+            writeln!(self.stdout)?;
+        }
+
+        writeln!(self.stdout, "Found {} issues.", Paint::red(issues.len()))?;
+        self.stdout.flush()?;
+
+        if self.opts.plan && !issues.is_empty() {
+            writeln!(self.stdout)?;
+            writeln!(self.stdout, "Fix plan")?;
+
+            for (index, issue) in issues.iter().enumerate() {
+                writeln!(self.stdout, "  {:>3}. {}", index + 1, fix(issue))?;
+            }
+
+            self.stdout.flush()?;
+        }
+
+        let mut issues_left = issues.len();
+
+        if self.opts.delete_orphans && !self.opts.yes && !orphans_dids.is_empty() {
+            writeln!(self.stdout)?;
+            writeln!(
+                self.stdout,
+                "Would delete {} orphan twins, run again with --yes to delete them:",
+                Paint::yellow(orphans_dids.len())
+            )?;
+
+            for twin_did in &orphans_dids {
+                writeln!(self.stdout, "  {}", twin_did)?;
+            }
+
+            self.stdout.flush()?;
+        } else if self.opts.delete_orphans && !orphans_dids.is_empty() {
+            writeln!(self.stdout)?;
+            writeln!(
+                self.stdout,
+                "Deleting {} orphan twins...",
+                Paint::yellow(orphans_dids.len())
+            )?;
+            self.stdout.flush()?;
+
+            let mut deletions = Box::pin(delete_twins(self.host_api.clone(), orphans_dids));
+            let mut index = 0;
+
+            while let Some((twin_did, result)) = deletions.next().await {
+                write_deletion(self.stdout, &twin_did, &result, index, self.opts.verbose)?;
+                index += 1;
+
+                if result.is_ok() {
+                    issues_left -= 1;
+                }
+            }
+
+            writeln!(self.stdout)?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        if issues_left > 0 {
+            anyhow::bail!("{} integrity issues found", issues_left);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::helpers::{write_mappings, write_progress};
+use crate::commands::RunnableCommand;
+use crate::operations::clone::{copy_last_values, describe_model_twins};
+use crate::operations::describe::TwinDescription;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::import::{import_twins, map_twins_dids, remap_twin};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct CloneModelArgs {
+    /// Configuration file of the source host, stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// Configuration file of the destination host, stored in the `configuration` folder
+    #[structopt(long)]
+    pub to_config: String,
+    /// The model DID
+    #[structopt(short, long)]
+    pub model_did: String,
+    /// Copy the last value stored on every feed, followed by `--follower-twin-did` on the source host
+    #[structopt(long, requires = "follower-twin-did")]
+    pub with_last_values: bool,
+    /// Optional. The follower twin DID of `--with-last-values`. It should be local to the source host
+    #[structopt(long)]
+    pub follower_twin_did: Option<String>,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+pub struct CloneModel<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: CloneModelArgs,
+    source: Arc<dyn HostApi>,
+    destination: Arc<dyn HostApi>,
+}
+
+impl<'a, W> CloneModel<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(
+        stdout: &'a mut W,
+        opts: CloneModelArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let source_settings = Settings::new(&opts.config, quiet)?;
+        let destination_settings = Settings::new(&opts.to_config, quiet)?;
+        let source = GrpcHostApi::new(AuthBuilder::new(source_settings));
+        let destination = GrpcHostApi::new(AuthBuilder::new(destination_settings));
+        Ok(Self::with_host_api(stdout, opts, source, destination))
+    }
+
+    /// Runs the command against the given host APIs, e.g. two `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: CloneModelArgs,
+        source: Arc<dyn HostApi>,
+        destination: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            source,
+            destination,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for CloneModel<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let model_twins = describe_model_twins(self.source.clone(), &self.opts.model_did).await?;
+        let descriptions = model_twins.descriptions;
+        // the twins that couldn't be described are never cloned
+        let mut failures = model_twins.failed;
+        let twins_count = descriptions.len() - 1 + failures.len();
+
+        writeln!(
+            self.stdout,
+            "Found {} twins for model {}. Cloning {} twins, the model included, to {}...",
+            Paint::yellow(twins_count),
+            Paint::blue(&self.opts.model_did),
+            Paint::yellow(twins_count + 1),
+            Paint::blue(self.destination.host_address()),
+        )?;
+        self.stdout.flush()?;
+
+        let twins_dids = descriptions
+            .iter()
+            .map(|description| description.twin_did.clone())
+            .collect::<Vec<String>>();
+        // the clones never take the DIDs of the originals, which stay on the source host
+        let mappings = map_twins_dids(self.destination.clone(), &twins_dids, false).await?;
+
+        writeln!(self.stdout)?;
+        write_mappings(self.stdout, &mappings)?;
+        writeln!(self.stdout)?;
+        self.stdout.flush()?;
+
+        let clones = descriptions
+            .iter()
+            .cloned()
+            .map(|description| remap_twin(description, &mappings))
+            .collect::<Vec<TwinDescription>>();
+
+        let mut twins_cloned = 0;
+        let mut upserts = Box::pin(import_twins(self.destination.clone(), clones));
+        let mut index = 0;
+
+        while let Some((twin_did, result)) = upserts.next().await {
+            let description = &descriptions[index];
+
+            let result = match (result, &self.opts.follower_twin_did) {
+                (Ok(_), Some(follower_twin_did)) if self.opts.with_last_values => copy_last_values(
+                    self.source.as_ref(),
+                    self.destination.as_ref(),
+                    description,
+                    &twin_did,
+                    follower_twin_did,
+                )
+                .await
+                .map(|_| ()),
+                (result, _) => result,
+            };
+
+            write_progress(
+                self.stdout,
+                "Cloning",
+                &twin_did,
+                &result,
+                index,
+                self.opts.verbose,
+            )?;
+            index += 1;
+
+            match result {
+                Ok(_) => twins_cloned += 1,
+                Err(e) => failures.push((twin_did, e)),
+            }
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "Cloned {} twins, {} failed.",
+            Paint::green(twins_cloned),
+            Paint::red(failures.len()),
+        )?;
+
+        for (twin_did, e) in &failures {
+            writeln!(self.stdout, "{} {:?}", twin_did, Paint::red(e))?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        if !failures.is_empty() {
+            anyhow::bail!("{} twins failed to clone", failures.len());
+        }
+
+        Ok(())
+    }
+}
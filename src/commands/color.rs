@@ -0,0 +1,58 @@
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+
+use yansi::Paint;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(anyhow::anyhow!("expected `auto`, `always` or `never`")),
+        }
+    }
+}
+
+/// See https://no-color.org, any non-empty value disables the colours.
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+impl ColorChoice {
+    /// Whether the colours are written to the standard output.
+    /// `auto` only colours a terminal, unless `NO_COLOR` is set.
+    pub fn stdout_enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => !no_color() && io::stdout().is_terminal(),
+        }
+    }
+
+    /// Whether the colours are written to the log, which goes to the standard error.
+    pub fn stderr_enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => !no_color() && io::stderr().is_terminal(),
+        }
+    }
+
+    /// Enables or disables `Paint` globally, for every command.
+    pub fn apply(self) {
+        match self.stdout_enabled() {
+            true => Paint::enable(),
+            false => Paint::disable(),
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::Arc;
 use std::{io, marker};
 use structopt::StructOpt;
 use yansi::Paint;
 
-use iotics_grpc_client::twin::list::list_all_twins;
-
-use crate::commands::helpers::delete_and_log_twin;
-use crate::commands::settings::{AuthBuilder, Settings};
+use crate::commands::helpers::write_deletion;
 use crate::commands::RunnableCommand;
+use crate::operations::delete::delete_twins;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::twins::list_twins;
+use crate::settings::{AuthBuilder, Settings};
 
 #[derive(Debug, StructOpt)]
 pub struct DeleteAllTwinsArgs {
@@ -25,7 +28,7 @@ where
 {
     stdout: &'a mut W,
     opts: DeleteAllTwinsArgs,
-    settings: Settings,
+    host_api: Arc<dyn HostApi>,
     twins_found: usize,
     twins_deleted: usize,
 }
@@ -34,15 +37,29 @@ impl<'a, W> DeleteAllTwins<'a, W>
 where
     W: io::Write + marker::Send,
 {
-    pub fn new(stdout: &'a mut W, opts: DeleteAllTwinsArgs) -> Result<Self, anyhow::Error> {
-        let settings = Settings::new(&opts.config, stdout)?;
-        Ok(Self {
+    pub fn new(
+        stdout: &'a mut W,
+        opts: DeleteAllTwinsArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: DeleteAllTwinsArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
             stdout,
             opts,
-            settings,
+            host_api,
             twins_found: 0,
             twins_deleted: 0,
-        })
+        }
     }
 }
 
@@ -52,17 +69,10 @@ where
     W: io::Write + marker::Send,
 {
     async fn run(mut self) -> Result<(), anyhow::Error> {
-        let auth_builder = AuthBuilder::new(self.settings.clone());
-
-        let response = list_all_twins(auth_builder.clone()).await;
+        let response = list_twins(self.host_api.clone()).await;
 
         match response {
-            Ok(twins) => {
-                let twins_dids = twins
-                    .into_iter()
-                    .map(|twin| twin.id.expect("this should not happen").value)
-                    .collect::<Vec<String>>();
-
+            Ok(twins_dids) => {
                 writeln!(
                     self.stdout,
                     "Found {} twins. Deleting...",
@@ -70,15 +80,16 @@ where
                 )?;
                 self.stdout.flush()?;
 
-                for twin_did in twins_dids {
-                    let result = delete_and_log_twin(
+                let mut deletions = Box::pin(delete_twins(self.host_api.clone(), twins_dids));
+
+                while let Some((twin_did, result)) = deletions.next().await {
+                    write_deletion(
                         self.stdout,
-                        auth_builder.clone(),
                         &twin_did,
+                        &result,
                         self.twins_found,
                         self.opts.verbose,
-                    )
-                    .await;
+                    )?;
 
                     self.twins_found += 1;
 
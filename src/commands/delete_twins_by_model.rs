@@ -1,15 +1,16 @@
 use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::Arc;
 use std::{io, marker};
 use structopt::StructOpt;
 use yansi::Paint;
 
-use iotics_grpc_client::common::{Property, Scope, Uri, Value};
-use iotics_grpc_client::search::{search, Filter};
-use iotics_grpc_client::twin::PAGE_SIZE;
-
-use crate::commands::helpers::delete_and_log_twin;
-use crate::commands::settings::{AuthBuilder, Settings};
+use crate::commands::helpers::write_deletion;
 use crate::commands::RunnableCommand;
+use crate::operations::delete::delete_twins;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::twins::search_twins_by_model;
+use crate::settings::{AuthBuilder, Settings};
 
 #[derive(Debug, StructOpt)]
 pub struct DeleteTwinsByModelArgs {
@@ -33,20 +34,34 @@ where
 {
     stdout: &'a mut W,
     opts: DeleteTwinsByModelArgs,
-    settings: Settings,
+    host_api: Arc<dyn HostApi>,
 }
 
 impl<'a, W> DeleteTwinsByModel<'a, W>
 where
     W: io::Write + marker::Send,
 {
-    pub fn new(stdout: &'a mut W, opts: DeleteTwinsByModelArgs) -> Result<Self, anyhow::Error> {
-        let settings = Settings::new(&opts.config, stdout)?;
-        Ok(Self {
+    pub fn new(
+        stdout: &'a mut W,
+        opts: DeleteTwinsByModelArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: DeleteTwinsByModelArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
             stdout,
             opts,
-            settings,
-        })
+            host_api,
+        }
     }
 }
 
@@ -56,44 +71,13 @@ where
     W: io::Write + marker::Send,
 {
     async fn run(self) -> Result<(), anyhow::Error> {
-        let auth_builder = AuthBuilder::new(self.settings.clone());
-
-        let mut stream = search(
-            auth_builder.clone(),
-            Filter {
-                properties: vec![Property {
-                    key: "https://data.iotics.com/app#model".to_string(),
-                    value: Some(Value::UriValue(Uri {
-                        value: self.opts.model_did.clone(),
-                    })),
-                }],
-                location: None,
-                text: None,
-            },
-            Scope::Local,
-            None,
-        )
-        .await?;
-
+        let mut twins_search =
+            search_twins_by_model(self.host_api.as_ref(), &self.opts.model_did).await?;
         let mut twins_dids = Vec::new();
 
-        while let Some(response) = stream.recv().await {
-            match response {
-                Ok(page) => {
-                    if let Some(payload) = page.payload {
-                        if payload.twins.len() < PAGE_SIZE as usize {
-                            // this must be the last page, close the stream
-                            stream.close();
-                        }
-
-                        twins_dids.extend(
-                            payload
-                                .twins
-                                .into_iter()
-                                .map(|twin| twin.id.expect("this should not happen").value),
-                        );
-                    }
-                }
+        while let Some(twin_did) = twins_search.next().await {
+            match twin_did {
+                Ok(twin_did) => twins_dids.push(twin_did),
                 Err(e) => {
                     writeln!(self.stdout, "{:?}", Paint::red(e))?;
                     self.stdout.flush()?;
@@ -110,16 +94,19 @@ where
         self.stdout.flush()?;
 
         let mut twins_deleted = 0;
+        let mut deletions = Box::pin(delete_twins(self.host_api.clone(), twins_dids));
+        let mut twins_found = 0;
 
-        for (twins_found, twin_did) in twins_dids.into_iter().enumerate() {
-            let result = delete_and_log_twin(
+        while let Some((twin_did, result)) = deletions.next().await {
+            write_deletion(
                 self.stdout,
-                auth_builder.clone(),
                 &twin_did,
+                &result,
                 twins_found,
                 self.opts.verbose,
-            )
-            .await;
+            )?;
+
+            twins_found += 1;
 
             if result.is_ok() {
                 twins_deleted += 1;
@@ -136,8 +123,10 @@ where
         self.stdout.flush()?;
 
         if self.opts.delete_model {
-            let twin_did = self.opts.model_did.clone();
-            delete_and_log_twin(self.stdout, auth_builder.clone(), &twin_did, 0, true).await?;
+            let twin_did = &self.opts.model_did;
+            let result = self.host_api.delete_twin(twin_did).await;
+            write_deletion(self.stdout, twin_did, &result, 0, true)?;
+            result?;
         }
 
         writeln!(self.stdout)?;
@@ -1,13 +1,34 @@
 use async_trait::async_trait;
 use iotics_grpc_client::common::{HostId, TwinId};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::{io, marker};
 use structopt::StructOpt;
 use yansi::Paint;
 
-use iotics_grpc_client::twin::describe::describe_twin;
-
-use crate::commands::settings::{AuthBuilder, Settings};
+use crate::commands::rendering::{prefixed_uri, property_value, write_table};
 use crate::commands::RunnableCommand;
+use crate::operations::describe::{fetch_twin_description, TwinDescription};
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowMode {
+    Rendered,
+    Raw,
+}
+
+impl FromStr for ShowMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "rendered" => Ok(ShowMode::Rendered),
+            "raw" => Ok(ShowMode::Raw),
+            _ => Err(anyhow::anyhow!("expected `rendered` or `raw`")),
+        }
+    }
+}
 
 #[derive(Debug, StructOpt)]
 pub struct DescribeTwinArgs {
@@ -20,31 +41,153 @@ pub struct DescribeTwinArgs {
     /// Optional. The ID of the remote host, if the twin to be described is not stored on the host that's making the request
     #[structopt(long)]
     pub host_id: Option<String>,
+    /// How to show the description: `rendered` or `raw` for the full API response
+    #[structopt(long, default_value = "rendered")]
+    pub show: ShowMode,
     /// Logging level
     #[structopt(short, long)]
     pub verbose: bool,
 }
 
+pub fn write_twin_description<W>(
+    stdout: &mut W,
+    description: &TwinDescription,
+) -> Result<(), io::Error>
+where
+    W: io::Write + ?Sized,
+{
+    let location = match description.location {
+        Some((lat, lon)) => format!("{}, {}", lat, lon),
+        None => "".to_string(),
+    };
+
+    writeln!(
+        stdout,
+        "{:12}{}",
+        "Twin",
+        Paint::green(&description.twin_did)
+    )?;
+    writeln!(
+        stdout,
+        "{:12}{}",
+        "Label",
+        Paint::blue(description.label().unwrap_or_default())
+    )?;
+    writeln!(
+        stdout,
+        "{:12}{}",
+        "Host",
+        description.host_id.as_deref().unwrap_or("local")
+    )?;
+    writeln!(
+        stdout,
+        "{:12}{}",
+        "Visibility",
+        description.visibility.as_deref().unwrap_or("")
+    )?;
+    writeln!(stdout, "{:12}{}", "Location", location)?;
+
+    writeln!(stdout)?;
+    writeln!(
+        stdout,
+        "Properties ({})",
+        Paint::yellow(description.properties.len())
+    )?;
+    let rows = description
+        .properties
+        .iter()
+        .map(|property| vec![prefixed_uri(&property.key), property_value(&property.value)])
+        .collect::<Vec<Vec<String>>>();
+    write_table(stdout, "  ", &["Key", "Value"], &rows)?;
+
+    for (title, points) in [
+        ("Feeds", &description.feeds),
+        ("Inputs", &description.inputs),
+    ] {
+        writeln!(stdout)?;
+        writeln!(stdout, "{} ({})", title, Paint::yellow(points.len()))?;
+
+        let mut rows = Vec::new();
+
+        for point in points {
+            let store_last = match point.store_last {
+                Some(true) => "store last",
+                _ => "",
+            };
+
+            if point.values.is_empty() {
+                rows.push(vec![
+                    point.id.clone(),
+                    store_last.to_string(),
+                    "".to_string(),
+                    "".to_string(),
+                    "".to_string(),
+                    "".to_string(),
+                ]);
+            }
+
+            for (index, value) in point.values.iter().enumerate() {
+                let (id, store_last) = match index {
+                    0 => (point.id.clone(), store_last.to_string()),
+                    _ => ("".to_string(), "".to_string()),
+                };
+
+                rows.push(vec![
+                    id,
+                    store_last,
+                    value.label.clone(),
+                    value.data_type.clone(),
+                    prefixed_uri(&value.unit),
+                    value.comment.clone(),
+                ]);
+            }
+        }
+
+        write_table(
+            stdout,
+            "  ",
+            &["ID", "", "Value", "Type", "Unit", "Comment"],
+            &rows,
+        )?;
+    }
+
+    Ok(())
+}
+
 pub struct DescribeTwin<'a, W>
 where
     W: io::Write + marker::Send,
 {
     stdout: &'a mut W,
     opts: DescribeTwinArgs,
-    settings: Settings,
+    host_api: Arc<dyn HostApi>,
 }
 
 impl<'a, W> DescribeTwin<'a, W>
 where
     W: io::Write + marker::Send,
 {
-    pub fn new(stdout: &'a mut W, opts: DescribeTwinArgs) -> Result<Self, anyhow::Error> {
-        let settings = Settings::new(&opts.config, stdout)?;
-        Ok(Self {
+    pub fn new(
+        stdout: &'a mut W,
+        opts: DescribeTwinArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: DescribeTwinArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
             stdout,
             opts,
-            settings,
-        })
+            host_api,
+        }
     }
 }
 
@@ -54,28 +197,49 @@ where
     W: io::Write + marker::Send,
 {
     async fn run(mut self) -> Result<(), anyhow::Error> {
-        let auth_builder = AuthBuilder::new(self.settings.clone());
-
-        let response = describe_twin(
-            auth_builder.clone(),
-            TwinId {
-                value: self.opts.twin_did,
-            },
-            self.opts.host_id.map(|host_id| HostId { value: host_id }),
-        )
-        .await;
-
-        match response {
-            Ok(result) => {
-                writeln!(self.stdout, "{:#?}", Paint::green(result))?;
-                self.stdout.flush()?;
+        match self.opts.show {
+            ShowMode::Raw => {
+                let response = self
+                    .host_api
+                    .describe_twin(
+                        TwinId {
+                            value: self.opts.twin_did,
+                        },
+                        self.opts.host_id.map(|host_id| HostId { value: host_id }),
+                    )
+                    .await;
+
+                match response {
+                    Ok(twin_meta) => {
+                        writeln!(self.stdout, "{}", Paint::green(twin_meta.raw))?;
+                    }
+                    Err(e) => {
+                        writeln!(self.stdout, "{:?}", Paint::red(e))?;
+                    }
+                }
             }
-            Err(e) => {
-                writeln!(self.stdout, "{:?}", Paint::red(e))?;
-                self.stdout.flush()?;
+            ShowMode::Rendered => {
+                let description = fetch_twin_description(
+                    self.host_api.clone(),
+                    &self.opts.twin_did,
+                    self.opts.host_id,
+                )
+                .await;
+
+                match description {
+                    Ok(description) => {
+                        writeln!(self.stdout)?;
+                        write_twin_description(self.stdout, &description)?;
+                    }
+                    Err(e) => {
+                        writeln!(self.stdout, "{:?}", Paint::red(e))?;
+                    }
+                }
             }
         }
 
+        self.stdout.flush()?;
+
         Ok(())
     }
 }
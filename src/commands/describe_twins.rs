@@ -0,0 +1,183 @@
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use log::error;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::rendering::{write_table, OutputFormat};
+use crate::commands::twin_selector::TwinSelector;
+use crate::commands::RunnableCommand;
+use crate::operations::describe::fetch_twin_description;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct DescribeTwinsArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    #[structopt(flatten)]
+    pub selector: TwinSelector,
+    /// Optional. The ID of the remote host, if the twins to be described are not stored on the host that's making the request
+    #[structopt(long)]
+    pub host_id: Option<String>,
+    /// The maximum number of twins described at the same time
+    #[structopt(long, default_value = "8")]
+    pub concurrency: usize,
+    /// Output format: `table` or `ndjson`
+    #[structopt(long, default_value = "table")]
+    pub output: OutputFormat,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+pub struct DescribeTwins<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: DescribeTwinsArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> DescribeTwins<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(
+        stdout: &'a mut W,
+        opts: DescribeTwinsArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: DescribeTwinsArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for DescribeTwins<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let twins_dids = self.opts.selector.select(self.host_api.clone()).await?;
+
+        if self.opts.output == OutputFormat::Table {
+            writeln!(
+                self.stdout,
+                "Describing {} twins...",
+                Paint::yellow(twins_dids.len()),
+            )?;
+            self.stdout.flush()?;
+        }
+
+        let host_id = self.opts.host_id.clone();
+        let mut results = stream::iter(twins_dids)
+            .map(|twin_did| {
+                let host_api = self.host_api.clone();
+                let host_id = host_id.clone();
+
+                async move {
+                    let result = fetch_twin_description(host_api, &twin_did, host_id).await;
+                    (twin_did, result)
+                }
+            })
+            .buffered(self.opts.concurrency.max(1));
+
+        let mut rows = Vec::new();
+        let mut failures = Vec::new();
+
+        while let Some((twin_did, result)) = results.next().await {
+            match self.opts.output {
+                OutputFormat::Ndjson => match &result {
+                    Ok(description) => {
+                        writeln!(self.stdout, "{}", description.to_json())?;
+                        self.stdout.flush()?;
+                    }
+                    // keep stdout a clean stream of descriptions
+                    Err(e) => error!("{} {:?}", twin_did, e),
+                },
+                OutputFormat::Table => {
+                    if let Ok(description) = &result {
+                        rows.push(vec![
+                            description.twin_did.clone(),
+                            description.label().unwrap_or_default(),
+                            description.visibility.clone().unwrap_or_default(),
+                            description.properties.len().to_string(),
+                            description.feeds.len().to_string(),
+                            description.inputs.len().to_string(),
+                        ]);
+                    }
+                }
+            }
+
+            if let Err(e) = result {
+                failures.push((twin_did, e));
+            }
+        }
+
+        if self.opts.output == OutputFormat::Table {
+            writeln!(self.stdout)?;
+            write_table(
+                self.stdout,
+                "",
+                &[
+                    "DID",
+                    "Label",
+                    "Visibility",
+                    "Properties",
+                    "Feeds",
+                    "Inputs",
+                ],
+                &rows,
+            )?;
+
+            writeln!(self.stdout)?;
+            writeln!(
+                self.stdout,
+                "Described {} twins, {} failed.",
+                Paint::green(rows.len()),
+                Paint::red(failures.len()),
+            )?;
+
+            for (twin_did, e) in &failures {
+                writeln!(self.stdout, "{} {:?}", twin_did, Paint::red(e))?;
+            }
+
+            writeln!(self.stdout)?;
+            writeln!(self.stdout, "Done.")?;
+            self.stdout.flush()?;
+        }
+
+        if !failures.is_empty() {
+            let twins_dids = failures
+                .iter()
+                .map(|(twin_did, _)| twin_did.as_str())
+                .collect::<Vec<&str>>();
+            anyhow::bail!(
+                "failed to describe {} twins: {}",
+                failures.len(),
+                twins_dids.join(", ")
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use trust_dns_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::Name;
+use trust_dns_resolver::TokioAsyncResolver;
+
+/// Resolves DNS records in-process, caching the TXT answers for the lifetime of the resolver.
+#[derive(Clone)]
+pub struct DnsResolver {
+    resolver: TokioAsyncResolver,
+    cache: Arc<Mutex<HashMap<String, Vec<String>>>>,
+}
+
+impl DnsResolver {
+    /// Uses the given nameserver if any, otherwise the system configuration.
+    pub fn new(nameserver: Option<SocketAddr>, timeout: Duration) -> Result<Self, anyhow::Error> {
+        let (config, mut options) = match nameserver {
+            Some(nameserver) => (
+                ResolverConfig::from_parts(
+                    None,
+                    vec![],
+                    NameServerConfigGroup::from_ips_clear(
+                        &[nameserver.ip()],
+                        nameserver.port(),
+                        true,
+                    ),
+                ),
+                ResolverOpts::default(),
+            ),
+            None => trust_dns_resolver::system_conf::read_system_conf()
+                .context("failed to read the system DNS configuration")?,
+        };
+
+        options.timeout = timeout;
+        options.attempts = 2;
+
+        let resolver = TokioAsyncResolver::tokio(config, options)
+            .context("failed to create the DNS resolver")?;
+
+        Ok(Self {
+            resolver,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Returns the text of every TXT record found for the name.
+    pub async fn lookup_txt(&self, name: &str) -> Result<Vec<String>, anyhow::Error> {
+        if let Some(records) = self.cached(name)? {
+            return Ok(records);
+        }
+
+        let lookup = self.resolver.txt_lookup(fqdn(name)?).await?;
+
+        let records = lookup
+            .iter()
+            .map(|txt| {
+                // a TXT record is made of one or more character strings, to be concatenated
+                let bytes = txt
+                    .iter()
+                    .flat_map(|data| data.iter().copied())
+                    .collect::<Vec<u8>>();
+
+                String::from_utf8_lossy(&bytes).trim().to_string()
+            })
+            .filter(|record| !record.is_empty())
+            .collect::<Vec<String>>();
+
+        self.cache
+            .lock()
+            .map_err(|_| anyhow::anyhow!("failed to lock the DNS cache mutex"))?
+            .insert(name.to_string(), records.clone());
+
+        Ok(records)
+    }
+
+    /// Returns the IP addresses the name resolves to.
+    pub async fn lookup_ip(&self, name: &str) -> Result<Vec<IpAddr>, anyhow::Error> {
+        let fqdn = format!("{}.", name.trim_end_matches('.'));
+        let lookup = self.resolver.lookup_ip(fqdn.as_str()).await?;
+
+        Ok(lookup.iter().collect())
+    }
+
+    fn cached(&self, name: &str) -> Result<Option<Vec<String>>, anyhow::Error> {
+        let cache_lock = self
+            .cache
+            .lock()
+            .map_err(|_| anyhow::anyhow!("failed to lock the DNS cache mutex"))?;
+
+        Ok(cache_lock.get(name).cloned())
+    }
+}
+
+/// The fully qualified name, so that the search domains are never appended.
+///
+/// The labels are taken as they are: host DIDs contain `:`, which the IDNA parsing of names rejects.
+fn fqdn(name: &str) -> Result<Name, anyhow::Error> {
+    let labels = name
+        .trim_end_matches('.')
+        .split('.')
+        .map(|label| label.as_bytes());
+
+    let mut fqdn = Name::from_labels(labels)?;
+    fqdn.set_fqdn(true);
+
+    Ok(fqdn)
+}
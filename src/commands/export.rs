@@ -0,0 +1,223 @@
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::helpers::write_progress;
+use crate::commands::twin_selector::TwinSelector;
+use crate::commands::RunnableCommand;
+use crate::operations::describe::fetch_twin_description;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::portable::PortableTwin;
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Yaml,
+    Json,
+    Ndjson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "yaml" => Ok(ExportFormat::Yaml),
+            "json" => Ok(ExportFormat::Json),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            _ => Err(anyhow::anyhow!("expected `yaml`, `json` or `ndjson`")),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ExportArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    #[structopt(flatten)]
+    pub selector: TwinSelector,
+    /// The archive file for `ndjson`, otherwise the directory that gets a file per twin
+    #[structopt(short, long)]
+    pub output: PathBuf,
+    /// Output format: `yaml`, `json` or `ndjson`
+    #[structopt(long, default_value = "ndjson")]
+    pub format: ExportFormat,
+    /// The maximum number of twins described at the same time
+    #[structopt(long, default_value = "8")]
+    pub concurrency: usize,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+/// Where the exported twins are written to.
+enum ExportSink {
+    Archive(BufWriter<File>),
+    Directory(PathBuf, ExportFormat),
+}
+
+impl ExportSink {
+    fn create(output: &Path, format: ExportFormat) -> Result<Self, anyhow::Error> {
+        match format {
+            ExportFormat::Ndjson => {
+                if let Some(parent) = output.parent() {
+                    if !parent.as_os_str().is_empty() {
+                        fs::create_dir_all(parent)?;
+                    }
+                }
+
+                Ok(ExportSink::Archive(BufWriter::new(File::create(output)?)))
+            }
+            _ => {
+                fs::create_dir_all(output)?;
+                Ok(ExportSink::Directory(output.to_path_buf(), format))
+            }
+        }
+    }
+
+    fn write(&mut self, twin: &PortableTwin) -> Result<(), anyhow::Error> {
+        match self {
+            ExportSink::Archive(writer) => {
+                serde_json::to_writer(&mut *writer, twin)?;
+                writeln!(writer)?;
+            }
+            ExportSink::Directory(directory, format) => {
+                // DIDs contain `:`, which isn't allowed in file names everywhere
+                let name = twin.twin_did.replace(':', "_");
+
+                let (extension, content) = match format {
+                    ExportFormat::Yaml => ("yaml", serde_yaml::to_string(twin)?),
+                    _ => ("json", serde_json::to_string_pretty(twin)? + "\n"),
+                };
+
+                fs::write(directory.join(format!("{}.{}", name, extension)), content)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), anyhow::Error> {
+        if let ExportSink::Archive(mut writer) = self {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Export<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: ExportArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> Export<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: ExportArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(stdout: &'a mut W, opts: ExportArgs, host_api: Arc<dyn HostApi>) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for Export<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let twins_dids = self.opts.selector.select(self.host_api.clone()).await?;
+
+        writeln!(
+            self.stdout,
+            "Exporting {} twins to {}...",
+            Paint::yellow(twins_dids.len()),
+            Paint::blue(self.opts.output.display()),
+        )?;
+        self.stdout.flush()?;
+
+        let mut sink = ExportSink::create(&self.opts.output, self.opts.format)?;
+
+        let mut results = stream::iter(twins_dids)
+            .map(|twin_did| {
+                let host_api = self.host_api.clone();
+
+                async move {
+                    let result = fetch_twin_description(host_api, &twin_did, None).await;
+                    (twin_did, result)
+                }
+            })
+            .buffered(self.opts.concurrency.max(1));
+
+        let mut twins_exported = 0;
+        let mut failures = Vec::new();
+        let mut index = 0;
+
+        while let Some((twin_did, result)) = results.next().await {
+            let result =
+                result.and_then(|description| sink.write(&PortableTwin::from(&description)));
+
+            write_progress(
+                self.stdout,
+                "Exporting",
+                &twin_did,
+                &result,
+                index,
+                self.opts.verbose,
+            )?;
+            index += 1;
+
+            match result {
+                Ok(_) => twins_exported += 1,
+                Err(e) => failures.push((twin_did, e)),
+            }
+        }
+
+        sink.finish()?;
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "Exported {} twins, {} failed.",
+            Paint::green(twins_exported),
+            Paint::red(failures.len()),
+        )?;
+
+        for (twin_did, e) in &failures {
+            writeln!(self.stdout, "{} {:?}", twin_did, Paint::red(e))?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        if !failures.is_empty() {
+            anyhow::bail!("{} twins failed to export", failures.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use iotics_grpc_client::common::{HostId, TwinId};
+
+use crate::commands::helpers::decode_feed_data;
+use crate::commands::RunnableCommand;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct FeedLastValueArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// The DIDs of the twins owning the feeds. Can be repeated
+    #[structopt(long = "twin-did", required = true)]
+    pub twin_dids: Vec<String>,
+    /// The feed IDs to fetch for each of the twins. Can be repeated
+    #[structopt(long = "feed-id", required = true)]
+    pub feed_ids: Vec<String>,
+    /// The follower twin DID. It should be local to the given host in the configuration file
+    #[structopt(long)]
+    pub follower_twin_did: String,
+    /// Optional. The ID of the remote host, if the twins are not stored on the host that's making the request
+    #[structopt(long)]
+    pub host_id: Option<String>,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+pub struct FeedLastValue<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: FeedLastValueArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> FeedLastValue<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(
+        stdout: &'a mut W,
+        opts: FeedLastValueArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: FeedLastValueArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for FeedLastValue<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let host_id = self
+            .opts
+            .host_id
+            .clone()
+            .map(|host_id| HostId { value: host_id });
+        let follower_twin_id = TwinId {
+            value: self.opts.follower_twin_did.clone(),
+        };
+
+        let mut fetches = Vec::new();
+
+        for twin_did in &self.opts.twin_dids {
+            for feed_id in &self.opts.feed_ids {
+                let host_api = self.host_api.clone();
+                let host_id = host_id.clone();
+                let follower_twin_id = follower_twin_id.clone();
+                let twin_did = twin_did.clone();
+                let feed_id = feed_id.clone();
+
+                fetches.push(async move {
+                    let result = host_api
+                        .fetch_last_stored(
+                            host_id,
+                            TwinId {
+                                value: twin_did.clone(),
+                            },
+                            feed_id.clone(),
+                            follower_twin_id,
+                        )
+                        .await;
+
+                    (twin_did, feed_id, result)
+                });
+            }
+        }
+
+        for (twin_did, feed_id, result) in join_all(fetches).await {
+            writeln!(
+                self.stdout,
+                "{} / {}",
+                Paint::blue(&twin_did),
+                Paint::blue(&feed_id),
+            )?;
+
+            match result {
+                Ok(feed_data) => match feed_data {
+                    Some(feed_data) => {
+                        writeln!(
+                            self.stdout,
+                            "shared at {:?} as {}",
+                            Paint::yellow(&feed_data.occurred_at),
+                            Paint::yellow(&feed_data.mime),
+                        )?;
+                        writeln!(
+                            self.stdout,
+                            "{}",
+                            Paint::green(decode_feed_data(&feed_data.mime, &feed_data.data)),
+                        )?;
+                    }
+                    None => {
+                        writeln!(self.stdout, "{}", Paint::yellow("no value stored"))?;
+                    }
+                },
+                Err(e) => {
+                    writeln!(self.stdout, "{:?}", Paint::red(e))?;
+                }
+            }
+
+            writeln!(self.stdout)?;
+            self.stdout.flush()?;
+        }
+
+        writeln!(self.stdout, "Done.")?;
+
+        Ok(())
+    }
+}
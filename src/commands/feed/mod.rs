@@ -0,0 +1,11 @@
+use structopt::StructOpt;
+
+pub mod last_value;
+
+use self::last_value::FeedLastValueArgs;
+
+#[derive(Debug, StructOpt)]
+pub enum FeedCommand {
+    /// Fetch the last value shared by one or many feeds, without following them
+    LastValue(FeedLastValueArgs),
+}
@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use iotics_grpc_client::common::FeedData;
+
+/// A single sample received by a follower, as stored in the archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedRecord {
+    /// Milliseconds since the recording started
+    pub received_after_ms: u64,
+    pub twin_did: String,
+    pub feed_id: String,
+    /// Seconds and nanoseconds of the `occurred_at` timestamp
+    pub occurred_at: Option<(i64, i32)>,
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+/// Appends feed samples to an archive file from any number of followers.
+#[derive(Clone)]
+pub struct FeedRecorder {
+    started_at: Instant,
+    sender: mpsc::UnboundedSender<FeedRecord>,
+}
+
+impl FeedRecorder {
+    /// Creates the archive file and starts the task writing into it.
+    /// The task finishes once every clone of the recorder has been dropped.
+    pub fn create(
+        path: &Path,
+    ) -> Result<(Self, JoinHandle<Result<(), anyhow::Error>>), anyhow::Error> {
+        let file = File::create(path)?;
+        let (sender, mut receiver) = mpsc::unbounded_channel::<FeedRecord>();
+
+        let handle = tokio::task::spawn_blocking(move || {
+            let mut writer = BufWriter::new(file);
+
+            while let Some(record) = receiver.blocking_recv() {
+                bincode::serialize_into(&mut writer, &record)?;
+                // flush every sample so that nothing is lost when the follow is interrupted
+                writer.flush()?;
+            }
+
+            Ok(())
+        });
+
+        Ok((
+            Self {
+                started_at: Instant::now(),
+                sender,
+            },
+            handle,
+        ))
+    }
+
+    pub fn record(
+        &self,
+        twin_did: &str,
+        feed_id: &str,
+        feed_data: &FeedData,
+    ) -> Result<(), anyhow::Error> {
+        let record = FeedRecord {
+            received_after_ms: self.started_at.elapsed().as_millis() as u64,
+            twin_did: twin_did.to_string(),
+            feed_id: feed_id.to_string(),
+            occurred_at: feed_data
+                .occurred_at
+                .as_ref()
+                .map(|occurred_at| (occurred_at.seconds, occurred_at.nanos)),
+            mime: feed_data.mime.clone(),
+            data: feed_data.data.clone(),
+        };
+
+        self.sender
+            .send(record)
+            .map_err(|_| anyhow::anyhow!("the archive writer has stopped"))
+    }
+}
+
+/// Reads all the records stored in an archive file.
+pub fn read_archive(path: &Path) -> Result<Vec<FeedRecord>, anyhow::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+
+    loop {
+        match bincode::deserialize_from::<_, FeedRecord>(&mut reader) {
+            Ok(record) => records.push(record),
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref io_error)
+                    if io_error.kind() == io::ErrorKind::UnexpectedEof =>
+                {
+                    break
+                }
+                _ => return Err(e.into()),
+            },
+        }
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use crate::operations::value_definitions::ValueDefinition;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    NotAnObject,
+    MissingField(String),
+    UnexpectedField(String),
+    TypeMismatch {
+        label: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::NotAnObject => write!(f, "sample is not a JSON object"),
+            ValidationIssue::MissingField(label) => write!(f, "missing field `{}`", label),
+            ValidationIssue::UnexpectedField(label) => write!(f, "unexpected field `{}`", label),
+            ValidationIssue::TypeMismatch {
+                label,
+                expected,
+                found,
+            } => write!(
+                f,
+                "field `{}` should be of type `{}`, found {}",
+                label, expected, found
+            ),
+        }
+    }
+}
+
+/// Checks a decoded sample against the values declared in the feed's metadata.
+pub fn validate_sample(
+    values: &[ValueDefinition],
+    sample: &serde_json::Value,
+) -> Vec<ValidationIssue> {
+    let fields = match sample.as_object() {
+        Some(fields) => fields,
+        None => return vec![ValidationIssue::NotAnObject],
+    };
+
+    let mut issues = Vec::new();
+
+    for value in values {
+        match fields.get(&value.label) {
+            Some(field) => {
+                if !matches_data_type(&value.data_type, field) {
+                    issues.push(ValidationIssue::TypeMismatch {
+                        label: value.label.clone(),
+                        expected: value.data_type.clone(),
+                        found: json_type_name(field).to_string(),
+                    });
+                }
+            }
+            None => issues.push(ValidationIssue::MissingField(value.label.clone())),
+        }
+    }
+
+    for label in fields.keys() {
+        if !values.iter().any(|value| &value.label == label) {
+            issues.push(ValidationIssue::UnexpectedField(label.clone()));
+        }
+    }
+
+    issues
+}
+
+// The data types are XSD types, see https://www.w3.org/TR/xmlschema-2/#built-in-datatypes
+fn matches_data_type(data_type: &str, field: &serde_json::Value) -> bool {
+    match data_type {
+        "boolean" => field.is_boolean(),
+        "integer" | "int" | "long" | "short" | "byte" | "nonNegativeInteger"
+        | "nonPositiveInteger" | "negativeInteger" | "positiveInteger" => {
+            field.is_i64() || field.is_u64()
+        }
+        "unsignedInt" | "unsignedLong" | "unsignedShort" | "unsignedByte" => field.is_u64(),
+        "decimal" | "float" | "double" => field.is_number(),
+        "string" | "anyURI" | "dateTime" | "date" | "time" | "duration" | "base64Binary"
+        | "hexBinary" => field.is_string(),
+        // unknown data types can't be checked
+        _ => true,
+    }
+}
+
+fn json_type_name(field: &serde_json::Value) -> &'static str {
+    match field {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(number) if number.is_f64() => "decimal",
+        serde_json::Value::Number(_) => "integer",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
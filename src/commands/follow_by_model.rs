@@ -1,16 +1,22 @@
 use async_trait::async_trait;
-use futures::future::join_all;
+use futures::StreamExt;
 use log::{error, info};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::{io, marker};
 use structopt::StructOpt;
 use yansi::Paint;
 
-use iotics_grpc_client::common::{Property, Scope, TwinId, Uri, Value};
-use iotics_grpc_client::interest::{create_interest_api_client, follow_with_client};
-use iotics_grpc_client::search::{search, Filter};
+use iotics_grpc_client::common::FeedData;
 
-use crate::commands::settings::{AuthBuilder, Settings};
+use crate::commands::feed_archive::FeedRecorder;
+use crate::commands::feed_validation::validate_sample;
 use crate::commands::RunnableCommand;
+use crate::operations::follow::{follow_by_model, FollowByModelOptions, FollowEvent};
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::value_definitions::ValueDefinition;
+use crate::settings::{AuthBuilder, Settings};
 
 #[derive(Debug, StructOpt)]
 pub struct FollowByModelArgs {
@@ -29,6 +35,12 @@ pub struct FollowByModelArgs {
     /// The maximum number of twins to follow
     #[structopt(long)]
     pub maximum_twins: usize,
+    /// Validate every received sample against the values declared in the feed metadata
+    #[structopt(long)]
+    pub validate: bool,
+    /// Optional. Record every received sample into the given archive file, to be used with `replay`
+    #[structopt(long)]
+    pub record: Option<PathBuf>,
     /// Logging level
     #[structopt(short, long)]
     pub verbose: bool,
@@ -40,20 +52,34 @@ where
 {
     stdout: &'a mut W,
     opts: FollowByModelArgs,
-    settings: Settings,
+    host_api: Arc<dyn HostApi>,
 }
 
 impl<'a, W> FollowByModel<'a, W>
 where
     W: io::Write + marker::Send,
 {
-    pub fn new(stdout: &'a mut W, opts: FollowByModelArgs) -> Result<Self, anyhow::Error> {
-        let settings = Settings::new(&opts.config, stdout)?;
-        Ok(Self {
+    pub fn new(
+        stdout: &'a mut W,
+        opts: FollowByModelArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: FollowByModelArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
             stdout,
             opts,
-            settings,
-        })
+            host_api,
+        }
     }
 }
 
@@ -63,197 +89,170 @@ where
     W: io::Write + marker::Send,
 {
     async fn run(self) -> Result<(), anyhow::Error> {
-        let auth_builder = AuthBuilder::new(self.settings.clone());
-
-        let filter = Filter {
-            properties: vec![Property {
-                key: "https://data.iotics.com/app#model".to_string(),
-                value: Some(Value::UriValue(Uri {
-                    value: self.opts.model_did.clone(),
-                })),
-            }],
-            location: None,
-            text: None,
-        };
-
         writeln!(
             self.stdout,
-            "Search criteria {}",
-            Paint::blue(&format!("{:?}", &filter)),
+            "Following the twins of model {}",
+            Paint::blue(&self.opts.model_did),
         )?;
         self.stdout.flush()?;
 
-        let mut search_stream = search(auth_builder.clone(), filter, Scope::Global, None).await?;
-
-        let mut count = 0;
-        let mut follow_handles = Vec::new();
-        let client = create_interest_api_client(auth_builder.clone()).await?;
-        let follower_twin_id = TwinId {
-            value: self.opts.follower_twin_did.clone(),
+        let (recorder, recorder_handle) = match &self.opts.record {
+            Some(path) => {
+                let (recorder, handle) = FeedRecorder::create(path)?;
+                writeln!(self.stdout, "Recording to {}", Paint::blue(path.display()))?;
+                self.stdout.flush()?;
+                (Some(recorder), Some(handle))
+            }
+            None => (None, None),
         };
 
-        while let Some(response) = search_stream.recv().await {
-            match response {
-                Ok(page) => {
-                    if let Some(payload) = page.payload {
-                        let twins = payload.twins;
-
-                        if !twins.is_empty() {
-                            writeln!(
-                                self.stdout,
-                                "Found {} twins for model {}. Following...",
-                                Paint::yellow(twins.len()),
-                                Paint::blue(&self.opts.model_did),
-                            )?;
-                            self.stdout.flush()?;
+        let mut events = follow_by_model(
+            self.host_api.clone(),
+            FollowByModelOptions {
+                model_did: self.opts.model_did.clone(),
+                feed_id: self.opts.feed_id.clone(),
+                follower_twin_did: self.opts.follower_twin_did.clone(),
+                maximum_twins: self.opts.maximum_twins,
+                describe_feed: self.opts.validate,
+            },
+        )
+        .await?;
 
-                            for twin in twins {
-                                let mut interest_channel = client.clone();
-                                let followed_twin_id =
-                                    twin.id.expect("this should not happen").clone();
-                                let followed_host_id = payload.remote_host_id.clone();
-                                let followed_feed = self.opts.feed_id.clone();
-                                let follower_twin_id = follower_twin_id.clone();
-                                let verbose = self.opts.verbose;
-                                let followed_auth_builder = auth_builder.clone();
+        let target = |index: usize, twin_did: &str| {
+            format!(
+                "iotics_cli::Follower {:4} {} / {}",
+                Paint::yellow(index),
+                Paint::blue(twin_did),
+                Paint::blue(&self.opts.feed_id)
+            )
+        };
 
-                                let fut = async move {
-                                    let twin_did = followed_twin_id.value.clone();
+        let mut value_definitions: HashMap<usize, Vec<ValueDefinition>> = HashMap::new();
 
-                                    let target = format!(
-                                        "iotics_cli::Follower {:4} {} / {}",
-                                        Paint::yellow(count + 1),
-                                        Paint::blue(&twin_did),
-                                        Paint::blue(&followed_feed)
-                                    );
+        while let Some(event) = events.next().await {
+            match event {
+                FollowEvent::Found { host_id, twins } => {
+                    if twins > 0 {
+                        writeln!(
+                            self.stdout,
+                            "Found {} twins for model {}. Following...",
+                            Paint::yellow(twins),
+                            Paint::blue(&self.opts.model_did),
+                        )?;
+                        self.stdout.flush()?;
+                    } else if self.opts.verbose {
+                        writeln!(self.stdout, "Found 0 twins from {:?}", host_id)?;
+                        self.stdout.flush()?;
+                    }
+                }
+                FollowEvent::SearchFailed(e) => {
+                    writeln!(self.stdout, "{:?}", Paint::red(e))?;
+                    self.stdout.flush()?;
+                }
+                FollowEvent::Following { index, twin_id, .. } => {
+                    if self.opts.verbose {
+                        info!(target: &target(index, &twin_id.value), "started");
+                    }
+                }
+                FollowEvent::Described {
+                    index,
+                    twin_did,
+                    values,
+                } => match values {
+                    Ok(values) => {
+                        value_definitions.insert(index, values);
+                    }
+                    Err(e) => {
+                        error!(
+                            target: &target(index, &twin_did),
+                            "failed to describe feed: {:?}",
+                            Paint::red(e)
+                        );
+                    }
+                },
+                FollowEvent::Data {
+                    index,
+                    twin_did,
+                    feed_data,
+                } => {
+                    let target = target(index, &twin_did);
 
-                                    if verbose {
-                                        info!(target: &target, "started");
-                                    }
+                    if let Some(recorder) = &recorder {
+                        if let Err(e) = recorder.record(&twin_did, &self.opts.feed_id, &feed_data) {
+                            error!(target: &target, "failed to record: {:?}", Paint::red(e));
+                        }
+                    }
 
-                                    let mut follow_stream = follow_with_client(
-                                        followed_auth_builder.clone(),
-                                        &mut interest_channel,
-                                        followed_host_id,
-                                        followed_twin_id,
-                                        followed_feed,
-                                        follower_twin_id,
-                                        true,
-                                    )
-                                    .await;
+                    log_feed_data(
+                        &target,
+                        &feed_data,
+                        value_definitions.get(&index).map(Vec::as_slice),
+                        self.opts.verbose,
+                    );
+                }
+                FollowEvent::Failed {
+                    index,
+                    twin_did,
+                    error,
+                } => {
+                    error!(
+                        target: &target(index, &twin_did),
+                        "crashed: {:?}",
+                        Paint::red(error)
+                    );
+                }
+            }
+        }
 
-                                    match follow_stream.as_mut() {
-                                        Ok(follow_stream) => {
-                                            let mut active = true;
+        // dropping the last recorder lets the archive writer finish
+        drop(recorder);
 
-                                            while active {
-                                                let message = follow_stream.message().await;
+        if let Some(handle) = recorder_handle {
+            handle.await??;
+        }
 
-                                                match message {
-                                                    Ok(Some(result)) => {
-                                                        if let Some(payload) = result.payload {
-                                                            if let Some(feed_data) =
-                                                                payload.feed_data
-                                                            {
-                                                                if feed_data
-                                                                    .mime
-                                                                    .starts_with("application/json")
-                                                                {
-                                                                    let json_data: Result<
-                                                                        serde_json::Value,
-                                                                        serde_json::Error,
-                                                                    > = serde_json::from_slice(
-                                                                        &feed_data.data,
-                                                                    );
+        Ok(())
+    }
+}
 
-                                                                    match json_data {
-                                                                        Ok(json_data) => {
-                                                                            if verbose {
-                                                                                info!(
-                                                                                    target: &target,
-                                                                                    "got data with timestamp {:?} > {:?}",
-                                                                                    Paint::yellow(
-                                                                                        feed_data.occurred_at,
-                                                                                    ),
-                                                                                    Paint::green(
-                                                                                        json_data
-                                                                                    )
-                                                                                );
-                                                                            } else {
-                                                                                info!(
-                                                                                    target: &target,
-                                                                                    "got data with timestamp {:?}",
-                                                                                    Paint::yellow(
-                                                                                        feed_data.occurred_at,
-                                                                                    ),
-                                                                                );
-                                                                            }
-                                                                        }
-                                                                        Err(e) => {
-                                                                            error!(
-                                                                                target: &target,
-                                                                                "failed to deserialize: {:?}",
-                                                                                Paint::red(e)
-                                                                            );
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                    Ok(None) => {
-                                                        error!(target: &target, "got message None");
-                                                    }
-                                                    Err(e) => {
-                                                        active = false;
-                                                        error!(
-                                                            target: &target,
-                                                            "crashed: {:?}",
-                                                            Paint::red(e)
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            error!(
-                                                target: &target,
-                                                "failed to follow: {:?}",
-                                                Paint::red(e)
-                                            );
-                                        }
-                                    }
-                                };
+fn log_feed_data(
+    target: &str,
+    feed_data: &FeedData,
+    value_definitions: Option<&[ValueDefinition]>,
+    verbose: bool,
+) {
+    if !feed_data.mime.starts_with("application/json") {
+        return;
+    }
 
-                                let handle = tokio::spawn(fut);
+    let json_data: Result<serde_json::Value, serde_json::Error> =
+        serde_json::from_slice(&feed_data.data);
 
-                                count += 1;
-                                follow_handles.push(handle);
+    match json_data {
+        Ok(json_data) => {
+            if verbose {
+                info!(
+                    target: target,
+                    "got data with timestamp {:?} > {:?}",
+                    Paint::yellow(&feed_data.occurred_at),
+                    Paint::green(&json_data)
+                );
+            } else {
+                info!(
+                    target: target,
+                    "got data with timestamp {:?}",
+                    Paint::yellow(&feed_data.occurred_at),
+                );
+            }
 
-                                if count >= self.opts.maximum_twins {
-                                    search_stream.close();
-                                    break;
-                                }
-                            }
-                        } else if self.opts.verbose {
-                            writeln!(
-                                self.stdout,
-                                "Found 0 twins from {:?}",
-                                payload.remote_host_id
-                            )?;
-                            self.stdout.flush()?;
-                        }
-                    }
-                }
-                Err(e) => {
-                    writeln!(self.stdout, "{:?}", Paint::red(e))?;
-                    self.stdout.flush()?;
+            if let Some(value_definitions) = value_definitions {
+                for issue in validate_sample(value_definitions, &json_data) {
+                    error!(target: target, "invalid sample: {}", Paint::red(issue));
                 }
             }
         }
-
-        join_all(follow_handles).await;
-
-        Ok(())
+        Err(e) => {
+            error!(target: target, "failed to deserialize: {:?}", Paint::red(e));
+        }
     }
 }
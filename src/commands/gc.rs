@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use futures::StreamExt;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::helpers::write_deletion;
+use crate::commands::rendering::write_table;
+use crate::commands::RunnableCommand;
+use crate::operations::delete::delete_twins;
+use crate::operations::gc::{find_stale_twins, Activity, StaleCriteria};
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct GcArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// Optional. Delete the twins created before the date, as `2022-06-30` or RFC 3339
+    #[structopt(long, parse(try_from_str = parse_date))]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Optional. Delete the twins last updated before the date, as `2022-06-30` or RFC 3339
+    #[structopt(long, parse(try_from_str = parse_date))]
+    pub updated_before: Option<DateTime<Utc>>,
+    /// Optional. Delete the twins whose feeds haven't shared data for that many days. Only the feeds storing their last value tell
+    #[structopt(long)]
+    pub inactive_days: Option<u32>,
+    /// List the twins that would be deleted, without deleting them
+    #[structopt(long)]
+    pub dry_run: bool,
+    /// The maximum number of twins checked at the same time
+    #[structopt(long, default_value = "8")]
+    pub concurrency: usize,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+fn parse_date(value: &str) -> Result<DateTime<Utc>, anyhow::Error> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("expected a date as `2022-06-30` or RFC 3339"))?;
+
+    Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("this should not happen")))
+}
+
+fn format_date(date_time: Option<DateTime<Utc>>) -> String {
+    date_time
+        .map(|date_time| date_time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+pub struct Gc<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: GcArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> Gc<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: GcArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(stdout: &'a mut W, opts: GcArgs, host_api: Arc<dyn HostApi>) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for Gc<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let criteria = StaleCriteria {
+            created_before: self.opts.created_before,
+            updated_before: self.opts.updated_before,
+            inactive_since: self
+                .opts
+                .inactive_days
+                .map(|days| Utc::now() - Duration::days(days.into())),
+        };
+
+        if criteria.is_empty() {
+            anyhow::bail!("no criteria, use --created-before, --updated-before or --inactive-days");
+        }
+
+        let stale_twins =
+            find_stale_twins(self.host_api.clone(), &criteria, self.opts.concurrency).await?;
+
+        let rows = stale_twins
+            .stale
+            .iter()
+            .map(|age| {
+                let last_shared = match age.activity {
+                    Activity::Unknown => "".to_string(),
+                    Activity::NeverShared => "never".to_string(),
+                    Activity::LastShared(last_shared_at) => format_date(Some(last_shared_at)),
+                };
+
+                vec![
+                    age.twin_did.clone(),
+                    format_date(age.created_at),
+                    format_date(age.updated_at),
+                    last_shared,
+                ]
+            })
+            .collect::<Vec<Vec<String>>>();
+
+        if !rows.is_empty() {
+            write_table(
+                self.stdout,
+                "",
+                &["DID", "Created", "Updated", "Last shared"],
+                &rows,
+            )?;
+            writeln!(self.stdout)?;
+        }
+
+        writeln!(
+            self.stdout,
+            "Found {} stale twins out of {}.",
+            Paint::yellow(stale_twins.stale.len()),
+            Paint::yellow(stale_twins.checked),
+        )?;
+
+        for (twin_did, e) in &stale_twins.failed {
+            writeln!(self.stdout, "{} {:?}", twin_did, Paint::red(e))?;
+        }
+
+        self.stdout.flush()?;
+
+        let mut deletions_failed = 0;
+
+        if self.opts.dry_run {
+            writeln!(self.stdout, "Dry run, no twin deleted.")?;
+        } else if !stale_twins.stale.is_empty() {
+            let twins_dids = stale_twins
+                .stale
+                .into_iter()
+                .map(|age| age.twin_did)
+                .collect::<Vec<String>>();
+            let mut deletions = Box::pin(delete_twins(self.host_api.clone(), twins_dids));
+            let mut twins_deleted = 0;
+            let mut index = 0;
+
+            writeln!(self.stdout, "Deleting...")?;
+
+            while let Some((twin_did, result)) = deletions.next().await {
+                write_deletion(self.stdout, &twin_did, &result, index, self.opts.verbose)?;
+                index += 1;
+
+                match result {
+                    Ok(_) => twins_deleted += 1,
+                    Err(_) => deletions_failed += 1,
+                }
+            }
+
+            writeln!(self.stdout)?;
+            writeln!(self.stdout, "Deleted {} twins.", Paint::red(twins_deleted))?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        if deletions_failed > 0 {
+            anyhow::bail!("{} twins failed to be deleted", deletions_failed);
+        }
+
+        // a twin that couldn't be checked may be stale
+        if !stale_twins.failed.is_empty() {
+            anyhow::bail!("{} twins failed to be checked", stale_twins.failed.len());
+        }
+
+        Ok(())
+    }
+}
@@ -1,32 +1,44 @@
-use std::sync::Arc;
 use std::{io, marker};
 use yansi::Paint;
 
-use iotics_grpc_client::twin::crud::delete_twin;
+use crate::commands::rendering::write_table;
+use crate::operations::import::DidMapping;
 
-use crate::commands::settings::AuthBuilder;
+/// Writes the outcome of a twin deletion, a dot per twin unless verbose.
+pub fn write_deletion<W>(
+    stdout: &'_ mut W,
+    twin_did: &str,
+    result: &Result<(), anyhow::Error>,
+    prev_index: usize,
+    verbose: bool,
+) -> Result<(), io::Error>
+where
+    W: io::Write + marker::Send,
+{
+    write_progress(stdout, "Deleting", twin_did, result, prev_index, verbose)
+}
 
-pub async fn delete_and_log_twin<W>(
+/// Writes the outcome of an action on a twin, a dot per twin unless verbose.
+pub fn write_progress<W>(
     stdout: &'_ mut W,
-    auth_builder: Arc<AuthBuilder>,
+    action: &str,
     twin_did: &str,
+    result: &Result<(), anyhow::Error>,
     prev_index: usize,
     verbose: bool,
-) -> Result<(), anyhow::Error>
+) -> Result<(), io::Error>
 where
     W: io::Write + marker::Send,
 {
     if verbose {
-        write!(stdout, "Deleting twin {}... ", twin_did)?;
+        write!(stdout, "{} twin {}... ", action, twin_did)?;
     }
 
     if !verbose && prev_index != 0 && prev_index % 64 == 0 {
         writeln!(stdout)?;
     }
 
-    let result = delete_twin(auth_builder, twin_did).await;
-
-    match &result {
+    match result {
         Ok(_) => {
             if verbose {
                 writeln!(stdout, "{}", Paint::green("OK"))?;
@@ -43,7 +55,52 @@ where
         }
     };
 
-    stdout.flush()?;
+    stdout.flush()
+}
+
+/// Renders feed data according to its mime type, falling back to hex for binary data.
+pub fn decode_feed_data(mime: &str, data: &[u8]) -> String {
+    if mime.starts_with("application/json") {
+        if let Ok(json_data) = serde_json::from_slice::<serde_json::Value>(data) {
+            return serde_json::to_string_pretty(&json_data).expect("this should not happen");
+        }
+    }
+
+    if mime.starts_with("text/") || mime.starts_with("application/json") {
+        if let Ok(text) = std::str::from_utf8(data) {
+            return text.to_string();
+        }
+    }
+
+    let hex = data
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!("{} bytes: {}", data.len(), hex)
+}
+
+/// Writes the DID every twin gets on the host, and whether a new identity was created for it.
+pub fn write_mappings<W>(stdout: &'_ mut W, mappings: &[DidMapping]) -> Result<(), io::Error>
+where
+    W: io::Write + marker::Send,
+{
+    let rows = mappings
+        .iter()
+        .map(|mapping| {
+            let identity = match mapping.created {
+                true => "created",
+                false => "kept",
+            };
+
+            vec![
+                mapping.original_did.clone(),
+                mapping.twin_did.clone(),
+                identity.to_string(),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
 
-    result
+    write_table(stdout, "", &["Original DID", "DID", "Identity"], &rows)
 }
@@ -0,0 +1,379 @@
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{io, marker};
+use structopt::StructOpt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use yansi::Paint;
+
+use iotics_grpc_client::common::Scope;
+use iotics_grpc_client::search::Filter;
+
+use crate::commands::dns::DnsResolver;
+use crate::commands::list_hosts::host_probe::fetch_host_version;
+use crate::commands::probe::{ProbeEngine, ProbeOutcome};
+use crate::commands::RunnableCommand;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::settings::{AuthBuilder, NetworkSettings, Settings};
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct HostCheckArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// Optional. The hosts to check, as names or gRPC URLs. Can be repeated. Defaults to the configured host
+    #[structopt(long = "host")]
+    pub hosts: Vec<String>,
+    /// Optional. The name of the network the hosts belong to. Defaults to the network of the configured host
+    #[structopt(long)]
+    pub network: Option<String>,
+    /// The timeout of every check, in seconds
+    #[structopt(long, default_value = "10")]
+    pub timeout: u64,
+    /// The maximum number of hosts checked at the same time
+    #[structopt(long, default_value = "4")]
+    pub concurrency: usize,
+    /// Fail the TLS check if the certificate expires in fewer days than this
+    #[structopt(long, default_value = "14")]
+    pub min_certificate_days: i64,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+/// The number of checks run against every host.
+const CHECKS_COUNT: u32 = 6;
+
+struct CheckResult {
+    name: &'static str,
+    passed: bool,
+    detail: String,
+}
+
+impl CheckResult {
+    fn from_result(name: &'static str, result: Result<String, anyhow::Error>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                name,
+                passed: true,
+                detail,
+            },
+            Err(e) => Self {
+                name,
+                passed: false,
+                detail: format!("{}", e),
+            },
+        }
+    }
+}
+
+pub struct HostCheck<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: HostCheckArgs,
+    settings: Settings,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> HostCheck<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: HostCheckArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings.clone()));
+        Ok(Self::with_host_api(stdout, opts, settings, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: HostCheckArgs,
+        settings: Settings,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            settings,
+            host_api,
+        }
+    }
+}
+
+async fn with_timeout<F>(duration: Duration, fut: F) -> Result<String, anyhow::Error>
+where
+    F: Future<Output = Result<String, anyhow::Error>>,
+{
+    timeout(duration, fut)
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out after {:?}", duration))?
+}
+
+async fn check_dns(dns_resolver: &DnsResolver, host: &str) -> Result<String, anyhow::Error> {
+    let addresses = dns_resolver.lookup_ip(host).await?;
+
+    if addresses.is_empty() {
+        anyhow::bail!("no address found");
+    }
+
+    Ok(addresses
+        .iter()
+        .map(|address| address.to_string())
+        .collect::<Vec<String>>()
+        .join(", "))
+}
+
+/// Connects to the gRPC port and returns the TCP connection latency.
+async fn connect_tcp(host: &str, port: u16) -> Result<Duration, anyhow::Error> {
+    let started_at = Instant::now();
+    TcpStream::connect((host, port)).await?;
+
+    Ok(started_at.elapsed())
+}
+
+/// Connects to the gRPC port and returns the TLS handshake latency and the certificate expiry.
+async fn connect_tls(host: &str, port: u16) -> Result<(Duration, i64), anyhow::Error> {
+    let started_at = Instant::now();
+    let stream = TcpStream::connect((host, port)).await?;
+
+    let connector = tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+    let tls_stream = connector.connect(host, stream).await?;
+    let latency = started_at.elapsed();
+
+    let certificate = tls_stream
+        .get_ref()
+        .peer_certificate()?
+        .ok_or_else(|| anyhow::anyhow!("no certificate presented"))?;
+    let der = certificate.to_der()?;
+    let (_, certificate) = x509_parser::parse_x509_certificate(&der)
+        .map_err(|e| anyhow::anyhow!("invalid certificate: {}", e))?;
+
+    Ok((latency, certificate.validity().not_after.timestamp()))
+}
+
+fn check_certificate(not_after: i64, min_certificate_days: i64) -> Result<String, anyhow::Error> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let days_left = (not_after - now) / 86400;
+
+    if days_left < min_certificate_days {
+        anyhow::bail!("certificate expires in {} days", days_left);
+    }
+
+    Ok(format!("certificate expires in {} days", days_left))
+}
+
+async fn check_search(host_api: &dyn HostApi, timeout: Duration) -> Result<String, anyhow::Error> {
+    let started_at = Instant::now();
+
+    let mut pages = host_api
+        .search(
+            Filter {
+                properties: vec![],
+                location: None,
+                text: Some("iotics-cli-host-check".to_string()),
+            },
+            Scope::Local,
+            Some(timeout),
+        )
+        .await?;
+
+    // the first page is enough, dropping the stream ends the search
+    let page = pages.next().await;
+
+    match page {
+        Some(Ok(_)) => Ok(format!("{} ms", started_at.elapsed().as_millis())),
+        Some(Err(e)) => Err(e),
+        None => Err(anyhow::anyhow!("no answer before the timeout")),
+    }
+}
+
+/// Runs every check against the host, each of them within the timeout.
+async fn check_host(
+    host: &str,
+    network: &NetworkSettings,
+    dns_resolver: &DnsResolver,
+    host_api: &dyn HostApi,
+    opts: &HostCheckArgs,
+) -> Vec<CheckResult> {
+    let check_timeout = Duration::from_secs(opts.timeout);
+    let mut results = Vec::new();
+
+    results.push(CheckResult::from_result(
+        "dns",
+        with_timeout(check_timeout, check_dns(dns_resolver, host)).await,
+    ));
+
+    // the gRPC calls themselves are made by the auth and search checks
+    if network.grpc_scheme == "https" {
+        let tls = timeout(check_timeout, connect_tls(host, network.grpc_port))
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out after {:?}", check_timeout))
+            .and_then(|result| result);
+
+        match tls {
+            Ok((latency, not_after)) => {
+                results.push(CheckResult::from_result(
+                    "connect",
+                    Ok(format!(
+                        "port {}: TLS handshake in {} ms",
+                        network.grpc_port,
+                        latency.as_millis()
+                    )),
+                ));
+                results.push(CheckResult::from_result(
+                    "tls",
+                    check_certificate(not_after, opts.min_certificate_days),
+                ));
+            }
+            Err(e) => {
+                results.push(CheckResult::from_result("connect", Err(e)));
+                results.push(CheckResult::from_result(
+                    "tls",
+                    Err(anyhow::anyhow!("no TLS connection")),
+                ));
+            }
+        }
+    } else {
+        let tcp = timeout(check_timeout, connect_tcp(host, network.grpc_port))
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out after {:?}", check_timeout))
+            .and_then(|result| result);
+
+        results.push(CheckResult::from_result(
+            "connect",
+            tcp.map(|latency| {
+                format!(
+                    "port {}: connected in {} ms",
+                    network.grpc_port,
+                    latency.as_millis()
+                )
+            }),
+        ));
+    }
+
+    results.push(CheckResult::from_result(
+        "version",
+        with_timeout(check_timeout, fetch_host_version(&network.index_url(host))).await,
+    ));
+
+    let host_api = host_api.for_host(network.grpc_url(host));
+
+    let authentication = with_timeout(check_timeout, async {
+        host_api.authenticate().await?;
+        Ok("token accepted".to_string())
+    })
+    .await;
+    let authenticated = authentication.is_ok();
+    results.push(CheckResult::from_result("auth", authentication));
+
+    let search_result = match authenticated {
+        true => {
+            with_timeout(
+                check_timeout,
+                check_search(host_api.as_ref(), check_timeout),
+            )
+            .await
+        }
+        false => Err(anyhow::anyhow!("not authenticated")),
+    };
+    results.push(CheckResult::from_result("search", search_result));
+
+    results
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for HostCheck<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let network = self.settings.network(self.opts.network.as_deref())?;
+        let check_timeout = Duration::from_secs(self.opts.timeout);
+        let dns_resolver = DnsResolver::new(network.nameserver, check_timeout)?;
+
+        let hosts = match self.opts.hosts.is_empty() {
+            true => vec![self.settings.iotics.host_address.clone()],
+            false => self.opts.hosts.clone(),
+        };
+        let hosts = hosts
+            .iter()
+            .map(|host| network.host_from_grpc_url(host))
+            .collect::<Vec<String>>();
+
+        // every check has its own timeout, this only guards against a host hanging in between
+        let engine = ProbeEngine::new(self.opts.concurrency, check_timeout * CHECKS_COUNT);
+        let network = Arc::new(network);
+        let opts = Arc::new(self.opts.clone());
+        let mut host_results =
+            Box::pin(
+                engine.run(stream::iter(hosts), |host| {
+                    let network = network.clone();
+                    let dns_resolver = dns_resolver.clone();
+                    let host_api = self.host_api.clone();
+                    let opts = opts.clone();
+
+                    async move {
+                        check_host(&host, &network, &dns_resolver, host_api.as_ref(), &opts).await
+                    }
+                }),
+            );
+
+        let mut checks_passed = 0;
+        let mut checks_failed = 0;
+
+        while let Some((host, outcome)) = host_results.next().await {
+            writeln!(self.stdout)?;
+            writeln!(self.stdout, "{}", Paint::blue(&host))?;
+
+            let results = match outcome {
+                ProbeOutcome::Completed(results) => results,
+                ProbeOutcome::TimedOut => vec![CheckResult::from_result(
+                    "host",
+                    Err(anyhow::anyhow!("timed out")),
+                )],
+            };
+
+            for result in results {
+                let status = match result.passed {
+                    true => {
+                        checks_passed += 1;
+                        Paint::green("PASS")
+                    }
+                    false => {
+                        checks_failed += 1;
+                        Paint::red("FAIL")
+                    }
+                };
+
+                writeln!(
+                    self.stdout,
+                    "  {} {:8} {}",
+                    status, result.name, result.detail
+                )?;
+            }
+
+            self.stdout.flush()?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "{} checks passed, {} failed.",
+            Paint::green(checks_passed),
+            Paint::red(checks_failed),
+        )?;
+        self.stdout.flush()?;
+
+        if checks_failed > 0 {
+            anyhow::bail!("{} checks failed", checks_failed);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::list_hosts::report::parse_version;
+use crate::commands::list_hosts::snapshot::{HostSnapshot, HostsSnapshot};
+use crate::commands::RunnableCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct HostDiffArgs {
+    /// The older snapshot, saved with `list-hosts --snapshot`
+    pub old: PathBuf,
+    /// The newer snapshot, saved with `list-hosts --snapshot`
+    pub new: PathBuf,
+    /// Only report the twin count changes at least this large
+    #[structopt(long, default_value = "1")]
+    pub twins_threshold: usize,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+pub struct HostDiff<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: HostDiffArgs,
+}
+
+impl<'a, W> HostDiff<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: HostDiffArgs) -> Result<Self, anyhow::Error> {
+        Ok(Self { stdout, opts })
+    }
+
+    fn write_section(&mut self, title: &str, lines: &[String]) -> Result<(), io::Error> {
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "{} ({})", title, Paint::yellow(lines.len()))?;
+
+        for line in lines {
+            writeln!(self.stdout, "  {}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn describe_host(host: &HostSnapshot) -> String {
+    format!(
+        "{} {} {} {}",
+        host.host_did,
+        Paint::green(&host.url),
+        Paint::blue(host.known_version().unwrap_or("?")),
+        host.twins_count
+            .map(|twins_count| twins_count.to_string())
+            .unwrap_or_default()
+    )
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for HostDiff<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(mut self) -> Result<(), anyhow::Error> {
+        let old = HostsSnapshot::load(&self.opts.old)?;
+        let new = HostsSnapshot::load(&self.opts.new)?;
+
+        writeln!(
+            self.stdout,
+            "Comparing {} hosts from {} with {} hosts from {}",
+            Paint::yellow(old.hosts.len()),
+            Paint::blue(old.taken_at),
+            Paint::yellow(new.hosts.len()),
+            Paint::blue(new.taken_at),
+        )?;
+
+        let old_hosts = old
+            .hosts
+            .iter()
+            .map(|host| (host.host_did.as_str(), host))
+            .collect::<HashMap<&str, &HostSnapshot>>();
+        let new_hosts = new
+            .hosts
+            .iter()
+            .map(|host| (host.host_did.as_str(), host))
+            .collect::<HashMap<&str, &HostSnapshot>>();
+
+        let appeared = new
+            .hosts
+            .iter()
+            .filter(|host| !old_hosts.contains_key(host.host_did.as_str()))
+            .map(describe_host)
+            .collect::<Vec<String>>();
+        let disappeared = old
+            .hosts
+            .iter()
+            .filter(|host| !new_hosts.contains_key(host.host_did.as_str()))
+            .map(describe_host)
+            .collect::<Vec<String>>();
+
+        let mut upgraded = Vec::new();
+        let mut downgraded = Vec::new();
+        let mut twins_changed = Vec::new();
+
+        for new_host in &new.hosts {
+            let old_host = match old_hosts.get(new_host.host_did.as_str()) {
+                Some(old_host) => old_host,
+                None => continue,
+            };
+
+            // an unknown version is neither an upgrade nor a downgrade
+            if let (Some(old_version), Some(new_version)) =
+                (old_host.known_version(), new_host.known_version())
+            {
+                let line = format!(
+                    "{} {} {} -> {}",
+                    new_host.host_did,
+                    Paint::green(&new_host.url),
+                    Paint::blue(old_version),
+                    Paint::blue(new_version),
+                );
+
+                let change = match (parse_version(new_version), parse_version(old_version)) {
+                    (Some(new_version), Some(old_version)) => new_version.cmp(&old_version),
+                    _ => new_version.cmp(old_version),
+                };
+
+                match change {
+                    Ordering::Greater => upgraded.push(line),
+                    Ordering::Less => downgraded.push(line),
+                    Ordering::Equal => {}
+                }
+            }
+
+            if let (Some(old_count), Some(new_count)) = (
+                old_host.twins_count.map(|twins_count| twins_count.count),
+                new_host.twins_count.map(|twins_count| twins_count.count),
+            ) {
+                let change = new_count as i64 - old_count as i64;
+
+                if change.unsigned_abs() as usize >= self.opts.twins_threshold && change != 0 {
+                    twins_changed.push(format!(
+                        "{} {} {} -> {} ({:+})",
+                        new_host.host_did,
+                        Paint::green(&new_host.url),
+                        old_count,
+                        new_count,
+                        change,
+                    ));
+                }
+            }
+        }
+
+        self.write_section("Appeared", &appeared)?;
+        self.write_section("Disappeared", &disappeared)?;
+        self.write_section("Upgraded", &upgraded)?;
+        self.write_section("Downgraded", &downgraded)?;
+        self.write_section("Twins count changed", &twins_changed)?;
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+use structopt::StructOpt;
+
+pub mod check;
+pub mod diff;
+pub mod stats;
+
+use self::{check::HostCheckArgs, diff::HostDiffArgs, stats::HostStatsArgs};
+
+#[derive(Debug, StructOpt)]
+pub enum HostCommand {
+    /// Check the health of one or many hosts: DNS, TLS, gRPC, version, auth and search
+    Check(HostCheckArgs),
+    /// Compare two snapshots saved with `list-hosts --snapshot`
+    Diff(HostDiffArgs),
+    /// Break the twins of one or many hosts down by model, type, visibility and owner
+    Stats(HostStatsArgs),
+}
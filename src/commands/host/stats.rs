@@ -0,0 +1,313 @@
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use iotics_grpc_client::common::{HostId, Property, Value};
+use iotics_grpc_client::search::Filter;
+
+use crate::commands::dns::DnsResolver;
+use crate::commands::list_hosts::host_probe::{host_did, resolve_host_url};
+use crate::commands::list_hosts::list_hosts::search_hosts;
+use crate::commands::probe::{ProbeEngine, ProbeOutcome};
+use crate::commands::rendering::prefixed_uri;
+use crate::commands::RunnableCommand;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::twins::{search_local_pages, MODEL_PROPERTY, TYPE_PROPERTY};
+use crate::settings::{AuthBuilder, NetworkSettings, Settings};
+
+const CREATED_BY_PROPERTY: &str = "https://data.iotics.com/app#createdBy";
+
+const NONE_LABEL: &str = "(none)";
+const BAR_WIDTH: usize = 40;
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, StructOpt)]
+pub struct HostStatsArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// Optional. The hosts to aggregate, as names or gRPC URLs. Can be repeated. Defaults to the configured host
+    #[structopt(long = "host")]
+    pub hosts: Vec<String>,
+    /// Aggregate every host found in the network instead, like `list-hosts`
+    #[structopt(long, conflicts_with = "hosts")]
+    pub all_hosts: bool,
+    /// Optional. The name of the network the hosts belong to. Defaults to the network of the configured host
+    #[structopt(long)]
+    pub network: Option<String>,
+    /// The maximum number of hosts aggregated at the same time
+    #[structopt(long, default_value = "4")]
+    pub concurrency: usize,
+    /// The maximum time spent aggregating a single host, in seconds
+    #[structopt(long, default_value = "600")]
+    pub timeout: u64,
+    /// The number of rows shown per breakdown, the rest being summed up as `(other)`
+    #[structopt(long, default_value = "10")]
+    pub top: usize,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+/// The number of twins of a host per model, type, visibility and owner.
+#[derive(Default)]
+struct TwinsBreakdown {
+    total: usize,
+    by_model: HashMap<String, usize>,
+    by_type: HashMap<String, usize>,
+    by_visibility: HashMap<String, usize>,
+    by_owner: HashMap<String, usize>,
+}
+
+impl TwinsBreakdown {
+    fn add(&mut self, properties: &[Property], visibility: &str) {
+        self.total += 1;
+
+        let uri_values = |key: &str| {
+            properties
+                .iter()
+                .filter(|property| property.key == key)
+                .filter_map(|property| match &property.value {
+                    Some(Value::UriValue(uri)) => Some(prefixed_uri(&uri.value)),
+                    Some(Value::StringLiteralValue(string_literal)) => {
+                        Some(string_literal.value.clone())
+                    }
+                    Some(Value::LiteralValue(literal)) => Some(literal.value.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<String>>()
+        };
+
+        increment(&mut self.by_model, uri_values(MODEL_PROPERTY));
+        // a twin can have many types, each of them is counted
+        increment(&mut self.by_type, uri_values(TYPE_PROPERTY));
+        increment(&mut self.by_owner, uri_values(CREATED_BY_PROPERTY));
+        increment(&mut self.by_visibility, vec![visibility.to_string()]);
+    }
+}
+
+fn increment(counts: &mut HashMap<String, usize>, keys: Vec<String>) {
+    if keys.is_empty() {
+        *counts.entry(NONE_LABEL.to_string()).or_default() += 1;
+    }
+
+    for key in keys {
+        *counts.entry(key).or_default() += 1;
+    }
+}
+
+pub struct HostStats<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: HostStatsArgs,
+    settings: Settings,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> HostStats<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: HostStatsArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings.clone()));
+        Ok(Self::with_host_api(stdout, opts, settings, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: HostStatsArgs,
+        settings: Settings,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            settings,
+            host_api,
+        }
+    }
+
+    /// Finds the URL of every host of the network, reporting the ones that can't be found.
+    async fn discover_hosts(
+        &mut self,
+        network: &NetworkSettings,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let dns_resolver = DnsResolver::new(network.nameserver, DNS_TIMEOUT)?;
+        let hosts_ids = search_hosts(self.host_api.as_ref())
+            .await?
+            .collect::<Vec<Option<HostId>>>()
+            .await;
+
+        let mut hosts = Vec::new();
+
+        for remote_host_id in hosts_ids {
+            let host = match &remote_host_id {
+                Some(host_id) => resolve_host_url(&dns_resolver, &host_id.value, network).await,
+                None => Ok(self.host_api.host_address()),
+            };
+
+            match host {
+                Ok(host) => hosts.push(host),
+                Err(e) => {
+                    writeln!(self.stdout)?;
+                    writeln!(self.stdout, "{}", Paint::blue(host_did(&remote_host_id)))?;
+                    writeln!(self.stdout, "  {}", Paint::red(format!("{:?}", e)))?;
+                }
+            }
+        }
+
+        Ok(hosts)
+    }
+
+    fn write_histogram(
+        &mut self,
+        title: &str,
+        counts: &HashMap<String, usize>,
+        total: usize,
+    ) -> Result<(), io::Error> {
+        let mut rows = counts
+            .iter()
+            .map(|(label, count)| (label.as_str(), *count))
+            .collect::<Vec<(&str, usize)>>();
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        if rows.len() > self.opts.top {
+            let other = rows[self.opts.top..]
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<usize>();
+            rows.truncate(self.opts.top);
+            rows.push(("(other)", other));
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "  {}", Paint::yellow(title))?;
+
+        let max_count = rows.iter().map(|(_, count)| *count).max().unwrap_or(0);
+
+        for (label, count) in rows {
+            let bar_length = match max_count {
+                0 => 0,
+                _ => (count * BAR_WIDTH + max_count - 1) / max_count,
+            };
+            let percentage = match total {
+                0 => 0.0,
+                _ => count as f64 * 100.0 / total as f64,
+            };
+
+            writeln!(
+                self.stdout,
+                "    {:width$} {:>7} {:>5.1}%  {}",
+                Paint::green("█".repeat(bar_length)),
+                count,
+                percentage,
+                label,
+                width = BAR_WIDTH,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Searches the host for all its twins and aggregates them, in a single pass over the results.
+async fn fetch_breakdown(host_api: &dyn HostApi) -> Result<TwinsBreakdown, anyhow::Error> {
+    let mut pages = Box::pin(
+        search_local_pages(
+            host_api,
+            Filter {
+                properties: vec![],
+                location: None,
+                text: None,
+            },
+        )
+        .await?,
+    );
+
+    let mut breakdown = TwinsBreakdown::default();
+
+    while let Some(page) = pages.next().await {
+        for twin in page?.twins {
+            let visibility = twin
+                .visibility
+                .map(|visibility| format!("{:?}", visibility))
+                .unwrap_or_else(|| NONE_LABEL.to_string());
+
+            breakdown.add(&twin.properties, &visibility);
+        }
+    }
+
+    Ok(breakdown)
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for HostStats<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(mut self) -> Result<(), anyhow::Error> {
+        let network = self.settings.network(self.opts.network.as_deref())?;
+
+        let hosts = match (self.opts.all_hosts, self.opts.hosts.is_empty()) {
+            (true, _) => self.discover_hosts(&network).await?,
+            (false, true) => vec![self.settings.iotics.host_address.clone()],
+            (false, false) => self.opts.hosts.clone(),
+        };
+
+        let hosts = hosts
+            .iter()
+            .map(|host| network.host_from_grpc_url(host))
+            .collect::<Vec<String>>();
+
+        let engine = ProbeEngine::new(
+            self.opts.concurrency,
+            Duration::from_secs(self.opts.timeout),
+        );
+        let host_api = self.host_api.clone();
+        let mut breakdowns = Box::pin(engine.run(stream::iter(hosts), move |host| {
+            let host_api = host_api.for_host(network.grpc_url(&host));
+            async move { fetch_breakdown(host_api.as_ref()).await }
+        }));
+
+        while let Some((host, outcome)) = breakdowns.next().await {
+            writeln!(self.stdout)?;
+            writeln!(self.stdout, "{}", Paint::blue(&host))?;
+            self.stdout.flush()?;
+
+            let breakdown = match outcome {
+                ProbeOutcome::Completed(Ok(breakdown)) => breakdown,
+                ProbeOutcome::Completed(Err(e)) => {
+                    writeln!(self.stdout, "  {}", Paint::red(format!("{:?}", e)))?;
+                    continue;
+                }
+                ProbeOutcome::TimedOut => {
+                    writeln!(self.stdout, "  {}", Paint::red("timed out"))?;
+                    continue;
+                }
+            };
+
+            writeln!(self.stdout, "  {} twins", Paint::yellow(breakdown.total))?;
+
+            self.write_histogram("By model", &breakdown.by_model, breakdown.total)?;
+            self.write_histogram("By type", &breakdown.by_type, breakdown.total)?;
+            self.write_histogram("By visibility", &breakdown.by_visibility, breakdown.total)?;
+            self.write_histogram("By owner", &breakdown.by_owner, breakdown.total)?;
+            self.stdout.flush()?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::helpers::{write_mappings, write_progress};
+use crate::commands::RunnableCommand;
+use crate::operations::describe::TwinDescription;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::import::{import_twins, map_twins_dids, remap_twin};
+use crate::operations::portable::PortableTwin;
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct ImportArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// The NDJSON archive or the directory of YAML or JSON files written by `export`
+    #[structopt(short, long)]
+    pub input: PathBuf,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+/// Reads the twins of an NDJSON archive, or of a directory with a file per twin.
+fn read_archive(input: &Path) -> Result<Vec<PortableTwin>, anyhow::Error> {
+    if !input.is_dir() {
+        return fs::read_to_string(input)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(anyhow::Error::from))
+            .collect();
+    }
+
+    let mut paths = fs::read_dir(input)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, io::Error>>()?;
+    paths.sort();
+
+    let mut twins = Vec::new();
+
+    for path in paths {
+        let content = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml" | "json") => fs::read_to_string(&path)?,
+            _ => continue,
+        };
+
+        // YAML is a superset of JSON
+        let twin = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path.display(), e))?;
+        twins.push(twin);
+    }
+
+    Ok(twins)
+}
+
+pub struct Import<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: ImportArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> Import<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: ImportArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(stdout: &'a mut W, opts: ImportArgs, host_api: Arc<dyn HostApi>) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for Import<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let twins = read_archive(&self.opts.input)?;
+
+        writeln!(
+            self.stdout,
+            "Importing {} twins from {}...",
+            Paint::yellow(twins.len()),
+            Paint::blue(self.opts.input.display()),
+        )?;
+        self.stdout.flush()?;
+
+        let twins_dids = twins
+            .iter()
+            .map(|twin| twin.twin_did.clone())
+            .collect::<Vec<String>>();
+        let mappings = map_twins_dids(self.host_api.clone(), &twins_dids, true).await?;
+
+        writeln!(self.stdout)?;
+        write_mappings(self.stdout, &mappings)?;
+        writeln!(self.stdout)?;
+        self.stdout.flush()?;
+
+        let descriptions = twins
+            .iter()
+            .map(|twin| remap_twin(TwinDescription::from(twin), &mappings))
+            .collect::<Vec<TwinDescription>>();
+
+        let mut twins_imported = 0;
+        let mut failures = Vec::new();
+        let mut upserts = Box::pin(import_twins(self.host_api.clone(), descriptions));
+        let mut index = 0;
+
+        while let Some((twin_did, result)) = upserts.next().await {
+            write_progress(
+                self.stdout,
+                "Importing",
+                &twin_did,
+                &result,
+                index,
+                self.opts.verbose,
+            )?;
+            index += 1;
+
+            match result {
+                Ok(_) => twins_imported += 1,
+                Err(e) => failures.push((twin_did, e)),
+            }
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "Imported {} twins, {} failed.",
+            Paint::green(twins_imported),
+            Paint::red(failures.len()),
+        )?;
+
+        for (twin_did, e) in &failures {
+            writeln!(self.stdout, "{} {:?}", twin_did, Paint::red(e))?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        if !failures.is_empty() {
+            anyhow::bail!("{} twins failed to import", failures.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use iotics_grpc_client::common::HostId;
+use log::error;
+
+use crate::commands::dns::DnsResolver;
+use crate::commands::list_hosts::twins_count::{count_twins, TwinsCount, TwinsCountCache};
+use crate::commands::list_hosts::ListHostsArgs;
+use crate::operations::host_api::HostApi;
+use crate::settings::NetworkSettings;
+
+#[derive(Debug)]
+pub struct HostResult {
+    pub host_did: String,
+    /// `None` if the version was not requested or could not be fetched
+    pub version: Option<String>,
+    pub url: String,
+    pub twins_count: Option<TwinsCount>,
+    /// Whether the host answered the version or twins count probe. Hosts that are not probed are reachable
+    pub reachable: bool,
+}
+
+/// Everything needed to probe a host found by the search.
+pub struct HostProbe {
+    pub opts: ListHostsArgs,
+    pub host_api: Arc<dyn HostApi>,
+    pub dns_resolver: DnsResolver,
+    pub twins_count_cache: TwinsCountCache,
+    pub network: NetworkSettings,
+}
+
+impl HostProbe {
+    /// Returns `None` if the host URL could not be found.
+    pub async fn probe(&self, remote_host_id: Option<HostId>) -> Option<HostResult> {
+        let opts = &self.opts;
+        let network = &self.network;
+
+        let host_did = host_did(&remote_host_id);
+
+        let url = match remote_host_id {
+            Some(_) => get_host_url(opts, &self.dns_resolver, &host_did, network).await,
+            None => {
+                let url = self.host_api.host_address();
+                Some(network.host_from_grpc_url(&url))
+            }
+        }?;
+
+        let version = get_host_version(opts, &url, network).await;
+        let twins_count = get_twin_count(
+            opts,
+            &url,
+            network,
+            self.host_api.as_ref(),
+            &self.twins_count_cache,
+        )
+        .await;
+
+        let probed = opts.with_version || opts.with_twins;
+        let reachable = !probed || version.is_some() || twins_count.is_some();
+
+        Some(HostResult {
+            host_did,
+            version,
+            url,
+            twins_count,
+            reachable,
+        })
+    }
+}
+
+/// The local host has no remote host ID.
+pub fn host_did(remote_host_id: &Option<HostId>) -> String {
+    match remote_host_id {
+        Some(remote_host_id) => remote_host_id.value.clone(),
+        None => "?".to_string(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct VersionPayload {
+    pub version: String,
+}
+
+/// Fetches the version a host publishes in its `index.json`.
+pub(crate) async fn fetch_host_version(index_url: &str) -> Result<String, anyhow::Error> {
+    let response = reqwest::get(index_url)
+        .await
+        .context("get version url failed")?;
+    response.error_for_status_ref()?;
+
+    let json_response: VersionPayload = response.json().await?;
+    Ok(json_response.version)
+}
+
+/// Finds the URL of a remote host in the TXT record published for its DID.
+pub(crate) async fn resolve_host_url(
+    dns_resolver: &DnsResolver,
+    host_did: &str,
+    network: &NetworkSettings,
+) -> Result<String, anyhow::Error> {
+    let name = network.dns_name(host_did);
+
+    let records = dns_resolver.lookup_txt(&name).await?;
+
+    records
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("could not find host url"))
+}
+
+async fn get_host_url(
+    opts: &ListHostsArgs,
+    dns_resolver: &DnsResolver,
+    host_did: &str,
+    network: &NetworkSettings,
+) -> Option<String> {
+    let output = resolve_host_url(dns_resolver, host_did, network).await;
+
+    match output {
+        Ok(value) => Some(value),
+        Err(e) => {
+            if opts.verbose {
+                error!("could not find url for host {}: {}", host_did, e);
+            }
+            None
+        }
+    }
+}
+
+async fn get_host_version(
+    opts: &ListHostsArgs,
+    url: &str,
+    network: &NetworkSettings,
+) -> Option<String> {
+    let output: Result<Option<String>, anyhow::Error> = async {
+        match opts.with_version {
+            true => Ok(Some(fetch_host_version(&network.index_url(url)).await?)),
+            false => Ok(None),
+        }
+    }
+    .await;
+
+    match output {
+        Ok(value) => value,
+        Err(e) => {
+            if opts.verbose {
+                error!("could not get the version of host {}: {}", url, e);
+            }
+            None
+        }
+    }
+}
+
+async fn get_twin_count(
+    opts: &ListHostsArgs,
+    url: &str,
+    network: &NetworkSettings,
+    host_api: &dyn HostApi,
+    twins_count_cache: &TwinsCountCache,
+) -> Option<TwinsCount> {
+    let output: Result<Option<TwinsCount>, anyhow::Error> = async {
+        match opts.with_twins {
+            true => {
+                if let Some(twins_count) = twins_count_cache.get(url) {
+                    return Ok(Some(twins_count));
+                }
+
+                let host_api = host_api.for_host(network.grpc_url(url));
+                let twins_count = count_twins(host_api.as_ref(), opts.count_limit).await?;
+                twins_count_cache.insert(url, twins_count);
+
+                Ok(Some(twins_count))
+            }
+            false => Ok(None),
+        }
+    }
+    .await;
+
+    match output {
+        Ok(value) => value,
+        Err(e) => {
+            if opts.verbose {
+                error!("could not count the twins of host {}: {}", url, e);
+            }
+            None
+        }
+    }
+}
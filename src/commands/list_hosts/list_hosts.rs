@@ -1,12 +1,26 @@
-use actix::{Actor, Arbiter, System};
 use async_trait::async_trait;
-use std::{io, marker, thread};
+use futures::stream::{Stream, StreamExt};
+use log::error;
+use semver::VersionReq;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{io, marker};
 use structopt::StructOpt;
 
-use crate::commands::list_hosts::coordinator_actor::CoordinatorActor;
-use crate::commands::list_hosts::NetworkType;
-use crate::commands::settings::{AuthBuilder, Settings};
-use crate::RunnableCommand;
+use iotics_grpc_client::common::{HostId, Property, Scope, Uri, Value};
+use iotics_grpc_client::search::Filter;
+
+use crate::commands::dns::DnsResolver;
+use crate::commands::list_hosts::host_probe::{host_did, HostProbe};
+use crate::commands::list_hosts::report::{ExportFormat, HostsReport, SortBy};
+use crate::commands::list_hosts::snapshot::HostsSnapshot;
+use crate::commands::list_hosts::twins_count::TwinsCountCache;
+use crate::commands::probe::{ProbeEngine, ProbeOutcome};
+use crate::commands::RunnableCommand;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::settings::{AuthBuilder, Settings};
 
 #[derive(Debug, StructOpt, Clone)]
 pub struct ListHostsArgs {
@@ -19,72 +33,188 @@ pub struct ListHostsArgs {
     /// Fetch the twins count oh the host
     #[structopt(long)]
     pub with_twins: bool,
+    /// Optional. Stop counting the twins of a host at this number, reported as `>N`
+    #[structopt(long)]
+    pub count_limit: Option<usize>,
+    /// How long the twins count of a host is cached between runs, in seconds. 0, the default, disables the cache
+    #[structopt(long, default_value = "0")]
+    pub count_cache_ttl: u64,
+    /// Optional. The name of the network to search, either a preset (`prod`, `dev`) or one defined in the configuration file.
+    /// Defaults to the network of the configured host
+    #[structopt(long)]
+    pub network: Option<String>,
+    /// Optional. The nameserver used to discover the hosts URL, e.g. `127.0.0.1:5353`. Overrides the network nameserver
+    #[structopt(long)]
+    pub nameserver: Option<SocketAddr>,
+    /// The timeout of a DNS query, in seconds
+    #[structopt(long, default_value = "5")]
+    pub dns_timeout: u64,
+    /// The maximum time spent probing a single host, in seconds. Hosts that take longer are reported as timed out
+    #[structopt(long, default_value = "30")]
+    pub probe_timeout: u64,
+    /// The maximum number of hosts probed at the same time
+    #[structopt(long, default_value = "32")]
+    pub concurrency: usize,
+    /// Optional. Sort the hosts by `host`, `version` or `twins`. Defaults to the order in which they answered
+    #[structopt(long)]
+    pub sort_by: Option<SortBy>,
+    /// Optional. Only list the hosts whose version matches the requirement, e.g. `>=4.0.0`. Implies --with-version
+    #[structopt(long)]
+    pub filter_version: Option<VersionReq>,
+    /// Optional. Only list the hosts with at least the given number of twins. Implies --with-twins
+    #[structopt(long)]
+    pub min_twins: Option<usize>,
+    /// Show the full host URL instead of stripping the network DNS suffix
+    #[structopt(long)]
+    pub full_url: bool,
+    /// Output format: `table`, `csv` or `json`
+    #[structopt(long, default_value = "table")]
+    pub output: ExportFormat,
+    /// Optional. Save the hosts found to the given file, to be compared later with `host diff`. Implies --with-version and --with-twins
+    #[structopt(long)]
+    pub snapshot: Option<PathBuf>,
     /// Logging level
     #[structopt(short, long)]
     pub verbose: bool,
 }
 
-pub struct ListHosts<W>
+pub struct ListHosts<'a, W>
 where
-    W: io::Write + marker::Send + marker::Sync + 'static,
+    W: io::Write + marker::Send,
 {
-    stdout: W,
+    stdout: &'a mut W,
     opts: ListHostsArgs,
     settings: Settings,
+    host_api: Arc<dyn HostApi>,
 }
 
-impl<W> ListHosts<W>
+impl<'a, W> ListHosts<'a, W>
 where
-    W: io::Write + marker::Send + marker::Sync,
+    W: io::Write + marker::Send,
 {
-    pub fn new(stdout: W, opts: ListHostsArgs) -> Result<Self, anyhow::Error> {
-        let mut stdout = stdout;
-        let settings = Settings::new(&opts.config, &mut stdout)?;
-        Ok(Self {
+    pub fn new(stdout: &'a mut W, opts: ListHostsArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings.clone()));
+        Ok(Self::with_host_api(stdout, opts, settings, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockNetwork` host.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: ListHostsArgs,
+        settings: Settings,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
             stdout,
             opts,
             settings,
-        })
+            host_api,
+        }
     }
+}
 
-    fn run_sync(self) -> Result<(), anyhow::Error> {
-        thread::spawn(move || {
-            let system = System::new();
-            let stdout = Box::new(self.stdout);
-            let opts = self.opts.clone();
-            let settings = self.settings;
-
-            let network_type = if settings.iotics.host_address.contains(".dev.") {
-                NetworkType::Dev
-            } else {
-                NetworkType::Prod
-            };
-
-            let auth_builder = AuthBuilder::new(settings);
-
-            let execution = async {
-                let actor = CoordinatorActor::new(stdout, opts, auth_builder, network_type);
-
-                actor.start();
-            };
-
-            let arbiter = Arbiter::new();
-            arbiter.spawn(execution);
+/// Searches the network for the host twins, yielding the remote host ID of every host found.
+pub(crate) async fn search_hosts(
+    host_api: &dyn HostApi,
+) -> Result<impl Stream<Item = Option<HostId>>, anyhow::Error> {
+    let pages = host_api
+        .search(
+            Filter {
+                properties: vec![Property {
+                    key: "http://www.w3.org/1999/02/22-rdf-syntax-ns#type".to_string(),
+                    value: Some(Value::UriValue(Uri {
+                        value: "http://data.iotics.com/public#HostTwin".to_string(),
+                    })),
+                }],
+                location: None,
+                text: None,
+            },
+            Scope::Global,
+            Some(Duration::from_secs(10)),
+        )
+        .await?;
 
-            Ok(system.run()?)
-        })
-        .join()
-        .expect("Actix thread panicked")
-    }
+    Ok(pages.filter_map(|result| async move {
+        match result {
+            Ok(page) => Some(page.host_id),
+            Err(e) => {
+                error!("search error: {}", e);
+                None
+            }
+        }
+    }))
 }
 
 #[async_trait]
-impl<W> RunnableCommand for ListHosts<W>
+impl<'a, W> RunnableCommand for ListHosts<'a, W>
 where
-    W: io::Write + marker::Send + marker::Sync,
+    W: io::Write + marker::Send,
 {
     async fn run(self) -> Result<(), anyhow::Error> {
-        self.run_sync()?;
+        let mut opts = self.opts.clone();
+        // a snapshot is only worth comparing with the versions and the twins counts
+        opts.with_version |= opts.filter_version.is_some()
+            || opts.sort_by == Some(SortBy::Version)
+            || opts.snapshot.is_some();
+        opts.with_twins |= opts.min_twins.is_some()
+            || opts.sort_by == Some(SortBy::Twins)
+            || opts.snapshot.is_some();
+
+        let network = self.settings.network(opts.network.as_deref())?;
+        let nameserver = opts.nameserver.or(network.nameserver);
+
+        let dns_resolver = DnsResolver::new(nameserver, Duration::from_secs(opts.dns_timeout))?;
+        let twins_count_cache = TwinsCountCache::load(
+            Duration::from_secs(opts.count_cache_ttl),
+            &self.settings.iotics.agent_did,
+            opts.count_limit,
+        );
+
+        let engine = ProbeEngine::new(opts.concurrency, Duration::from_secs(opts.probe_timeout));
+
+        let host_probe = Arc::new(HostProbe {
+            opts: opts.clone(),
+            host_api: self.host_api.clone(),
+            dns_resolver,
+            twins_count_cache: twins_count_cache.clone(),
+            network: network.clone(),
+        });
+
+        let hosts = search_hosts(self.host_api.as_ref()).await?;
+        let mut results = Box::pin(engine.run(hosts, |remote_host_id| {
+            let host_probe = host_probe.clone();
+            async move { host_probe.probe(remote_host_id).await }
+        }));
+
+        let mut report = HostsReport::default();
+
+        while let Some((remote_host_id, outcome)) = results.next().await {
+            match outcome {
+                ProbeOutcome::Completed(Some(host)) => report.hosts.push(host),
+                ProbeOutcome::Completed(None) => report.unreachable += 1,
+                ProbeOutcome::TimedOut => {
+                    let host_did = host_did(&remote_host_id);
+
+                    if opts.verbose {
+                        error!("probing host {} timed out", host_did);
+                    }
+                    report.timed_out.push(host_did);
+                }
+            }
+        }
+
+        report.write(self.stdout, &opts, &network)?;
+
+        if opts.with_twins {
+            if let Err(e) = twins_count_cache.save() {
+                error!("failed to save the twins count cache: {:?}", e);
+            }
+        }
+
+        if let Some(path) = &opts.snapshot {
+            HostsSnapshot::from_report(&report).save(path)?;
+        }
 
         Ok(())
     }
@@ -1,13 +1,8 @@
-mod coordinator_actor;
-mod host_actor;
+pub(crate) mod host_probe;
 #[allow(clippy::module_inception)]
 pub mod list_hosts;
-mod messages;
+pub mod report;
+pub mod snapshot;
+pub mod twins_count;
 
 pub use list_hosts::*;
-
-#[derive(Clone)]
-pub enum NetworkType {
-    Prod,
-    Dev,
-}
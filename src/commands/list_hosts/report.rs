@@ -0,0 +1,242 @@
+use std::io;
+use std::str::FromStr;
+
+use semver::Version;
+use yansi::Paint;
+
+use crate::commands::list_hosts::host_probe::HostResult;
+use crate::commands::list_hosts::ListHostsArgs;
+use crate::settings::NetworkSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Host,
+    Version,
+    Twins,
+}
+
+impl FromStr for SortBy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "host" => Ok(SortBy::Host),
+            "version" => Ok(SortBy::Version),
+            "twins" => Ok(SortBy::Twins),
+            _ => Err(anyhow::anyhow!("expected `host`, `version` or `twins`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "table" => Ok(ExportFormat::Table),
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(anyhow::anyhow!("expected `table`, `csv` or `json`")),
+        }
+    }
+}
+
+/// Parses versions such as `v1.2.3` or `1.2`, which are not strictly semver.
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim().trim_start_matches('v');
+
+    Version::parse(version)
+        .or_else(|_| Version::parse(&format!("{}.0", version)))
+        .ok()
+}
+
+#[derive(Default)]
+pub struct HostsReport {
+    pub hosts: Vec<HostResult>,
+    pub unreachable: usize,
+    pub timed_out: Vec<String>,
+}
+
+impl HostsReport {
+    pub fn hosts_found(&self) -> usize {
+        self.hosts.len() + self.unreachable + self.timed_out.len()
+    }
+
+    /// The hosts that answered their probes.
+    pub fn reachable(&self) -> usize {
+        self.hosts.iter().filter(|host| host.reachable).count()
+    }
+
+    /// The hosts whose URL could not be found, or that answered none of their probes.
+    pub fn unreachable(&self) -> usize {
+        self.unreachable + self.hosts.iter().filter(|host| !host.reachable).count()
+    }
+
+    pub fn total_twins(&self) -> usize {
+        self.hosts
+            .iter()
+            .filter_map(|host| host.twins_count)
+            .map(|twins_count| twins_count.count)
+            .sum()
+    }
+
+    /// Returns the hosts to display, filtered and sorted according to the options.
+    pub fn selected_hosts(&self, opts: &ListHostsArgs) -> Vec<&HostResult> {
+        let mut hosts = self
+            .hosts
+            .iter()
+            .filter(|host| match &opts.filter_version {
+                Some(version_req) => host
+                    .version
+                    .as_deref()
+                    .and_then(parse_version)
+                    .map(|version| version_req.matches(&version))
+                    .unwrap_or(false),
+                None => true,
+            })
+            .filter(|host| match opts.min_twins {
+                Some(min_twins) => host
+                    .twins_count
+                    .map(|twins_count| twins_count.count >= min_twins)
+                    .unwrap_or(false),
+                None => true,
+            })
+            .collect::<Vec<&HostResult>>();
+
+        match opts.sort_by {
+            Some(SortBy::Host) => hosts.sort_by(|a, b| a.url.cmp(&b.url)),
+            Some(SortBy::Version) => {
+                hosts.sort_by_key(|host| host.version.as_deref().and_then(parse_version))
+            }
+            Some(SortBy::Twins) => hosts.sort_by(|a, b| b.twins_count.cmp(&a.twins_count)),
+            None => {}
+        }
+
+        hosts
+    }
+
+    pub fn write<W>(
+        &self,
+        stdout: &mut W,
+        opts: &ListHostsArgs,
+        network: &NetworkSettings,
+    ) -> Result<(), anyhow::Error>
+    where
+        W: io::Write + ?Sized,
+    {
+        let hosts = self.selected_hosts(opts);
+        let host_name = |host: &HostResult| match opts.full_url {
+            true => host.url.clone(),
+            false => network.short_host(&host.url),
+        };
+
+        match opts.output {
+            ExportFormat::Table => {
+                let host_width = hosts
+                    .iter()
+                    .map(|host| host_name(host).len())
+                    .max()
+                    .unwrap_or(0)
+                    .max(28);
+
+                writeln!(
+                    stdout,
+                    "{:4} {:host_width$} {:58} {:12} {:6}",
+                    "#",
+                    "Host",
+                    "DID",
+                    "Version",
+                    "Twins",
+                    host_width = host_width
+                )?;
+
+                for (index, host) in hosts.iter().enumerate() {
+                    let twins_count = match host.twins_count {
+                        Some(twins_count) => format!("{:>6}", twins_count.to_string()),
+                        None => "".to_string(),
+                    };
+
+                    writeln!(
+                        stdout,
+                        "{:4} {:host_width$} {:58} {:12} {:6}",
+                        Paint::yellow(index + 1),
+                        Paint::green(host_name(host)),
+                        host.host_did,
+                        Paint::blue(host.version.as_deref().unwrap_or_default()),
+                        twins_count,
+                        host_width = host_width
+                    )?;
+                }
+
+                writeln!(stdout)?;
+                writeln!(
+                    stdout,
+                    "Found {} hosts, {} reachable, {} unreachable, {} timed out, {} twins in total.",
+                    Paint::yellow(self.hosts_found()),
+                    Paint::green(self.reachable()),
+                    Paint::red(self.unreachable()),
+                    Paint::red(self.timed_out.len()),
+                    Paint::yellow(self.total_twins()),
+                )?;
+
+                if !self.timed_out.is_empty() {
+                    writeln!(stdout)?;
+                    writeln!(stdout, "Timed out:")?;
+
+                    for host_did in &self.timed_out {
+                        writeln!(stdout, "{}", Paint::red(host_did))?;
+                    }
+                }
+            }
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer.write_record(["host", "did", "version", "twins"])?;
+
+                for host in hosts {
+                    writer.write_record([
+                        host_name(host),
+                        host.host_did.clone(),
+                        host.version.clone().unwrap_or_default(),
+                        host.twins_count
+                            .map(|twins_count| twins_count.to_string())
+                            .unwrap_or_default(),
+                    ])?;
+                }
+
+                stdout.write_all(&writer.into_inner()?)?;
+            }
+            ExportFormat::Json => {
+                let json = serde_json::json!({
+                    "hosts": hosts.iter().map(|host| serde_json::json!({
+                        "host": host_name(host),
+                        "did": host.host_did,
+                        "version": host.version,
+                        "twins": host.twins_count.map(|twins_count| twins_count.count),
+                        "twinsCapped": host.twins_count.map(|twins_count| twins_count.capped),
+                    })).collect::<Vec<serde_json::Value>>(),
+                    "timedOut": self.timed_out,
+                    "totals": {
+                        "hostsFound": self.hosts_found(),
+                        "reachable": self.reachable(),
+                        "unreachable": self.unreachable(),
+                        "timedOut": self.timed_out.len(),
+                        "twins": self.total_twins(),
+                    },
+                });
+
+                writeln!(stdout, "{}", serde_json::to_string_pretty(&json)?)?;
+            }
+        }
+
+        stdout.flush()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::list_hosts::report::HostsReport;
+use crate::commands::list_hosts::twins_count::TwinsCount;
+
+/// The hosts found by `list-hosts` at a given time, saved to compare the network over time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostsSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub hosts: Vec<HostSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostSnapshot {
+    pub host_did: String,
+    pub url: String,
+    /// `None` if the version could not be fetched
+    pub version: Option<String>,
+    pub twins_count: Option<TwinsCount>,
+}
+
+impl HostSnapshot {
+    /// The version of the host, if it's known. Older snapshots store the unknown versions as `""`.
+    pub fn known_version(&self) -> Option<&str> {
+        self.version
+            .as_deref()
+            .filter(|version| !version.is_empty())
+    }
+}
+
+impl HostsSnapshot {
+    pub fn from_report(report: &HostsReport) -> Self {
+        Self {
+            taken_at: Utc::now(),
+            hosts: report
+                .hosts
+                .iter()
+                .map(|host| HostSnapshot {
+                    host_did: host.host_did.clone(),
+                    url: host.url.clone(),
+                    version: host.version.clone(),
+                    twins_count: host.twins_count,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
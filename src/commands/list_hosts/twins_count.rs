@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use iotics_grpc_client::search::Filter;
+
+use crate::operations::host_api::HostApi;
+use crate::operations::twins::search_local_pages;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TwinsCount {
+    pub count: usize,
+    /// The counting stopped at the limit, there may be more twins
+    pub capped: bool,
+}
+
+impl fmt::Display for TwinsCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.capped {
+            true => write!(f, ">{}", self.count),
+            false => write!(f, "{}", self.count),
+        }
+    }
+}
+
+/// Counts the twins of the host page by page with a local search, private twins included.
+///
+/// The search is dropped as soon as the count passes the limit, and the count is reported as `>limit`.
+pub async fn count_twins(
+    host_api: &dyn HostApi,
+    limit: Option<usize>,
+) -> Result<TwinsCount, anyhow::Error> {
+    let filter = Filter {
+        properties: vec![],
+        location: None,
+        text: None,
+    };
+    let mut pages = Box::pin(search_local_pages(host_api, filter).await?);
+    let mut count = 0;
+
+    while let Some(page) = pages.next().await {
+        count += page?.twins.len();
+
+        if let Some(limit) = limit {
+            if count > limit {
+                return Ok(TwinsCount {
+                    count: limit,
+                    capped: true,
+                });
+            }
+        }
+    }
+
+    Ok(TwinsCount {
+        count,
+        capped: false,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedTwinsCount {
+    twins_count: TwinsCount,
+    counted_at: DateTime<Utc>,
+}
+
+/// Twins counts per host, persisted between runs so that big hosts are not counted every time.
+///
+/// A count depends on the agent and on the limit it was counted with, so they're both part of the key.
+/// The cache is stored in the cache directory of the user, and is disabled with a zero TTL.
+#[derive(Clone)]
+pub struct TwinsCountCache {
+    path: Option<PathBuf>,
+    ttl: Duration,
+    agent_did: String,
+    limit: Option<usize>,
+    entries: Arc<Mutex<HashMap<String, CachedTwinsCount>>>,
+}
+
+/// `$XDG_CACHE_HOME/iotics-cli`, or `$HOME/.cache/iotics-cli`.
+fn cache_directory() -> Option<PathBuf> {
+    let cache_home = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    Some(cache_home.join("iotics-cli"))
+}
+
+impl TwinsCountCache {
+    /// Loads the cache, ignoring it if it's disabled, missing or unreadable.
+    pub fn load(ttl: Duration, agent_did: &str, limit: Option<usize>) -> Self {
+        let path = match ttl.is_zero() {
+            true => None,
+            false => cache_directory().map(|directory| directory.join("twins-count.json")),
+        };
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            ttl,
+            agent_did: agent_did.to_string(),
+            limit,
+            entries: Arc::new(Mutex::new(entries)),
+        }
+    }
+
+    fn key(&self, host: &str) -> String {
+        match self.limit {
+            Some(limit) => format!("{} {} {}", self.agent_did, host, limit),
+            None => format!("{} {}", self.agent_did, host),
+        }
+    }
+
+    pub fn get(&self, host: &str) -> Option<TwinsCount> {
+        let entries_lock = self.entries.lock().ok()?;
+        let entry = entries_lock.get(&self.key(host))?;
+        let age = (Utc::now() - entry.counted_at).to_std().ok()?;
+
+        match age < self.ttl {
+            true => Some(entry.twins_count),
+            false => None,
+        }
+    }
+
+    pub fn insert(&self, host: &str, twins_count: TwinsCount) {
+        if let Ok(mut entries_lock) = self.entries.lock() {
+            entries_lock.insert(
+                self.key(host),
+                CachedTwinsCount {
+                    twins_count,
+                    counted_at: Utc::now(),
+                },
+            );
+        }
+    }
+
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let entries_lock = self
+            .entries
+            .lock()
+            .map_err(|_| anyhow::anyhow!("failed to lock the twins count cache mutex"))?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&*entries_lock)?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(
+        agent_did: &str,
+        limit: Option<usize>,
+        entries: &Arc<Mutex<HashMap<String, CachedTwinsCount>>>,
+    ) -> TwinsCountCache {
+        TwinsCountCache {
+            path: None,
+            ttl: Duration::from_secs(60),
+            agent_did: agent_did.to_string(),
+            limit,
+            entries: entries.clone(),
+        }
+    }
+
+    #[test]
+    fn counts_are_cached_per_agent_and_limit() {
+        let entries = Arc::new(Mutex::new(HashMap::new()));
+        let twins_count = TwinsCount {
+            count: 10,
+            capped: true,
+        };
+
+        cache("did:iotics:agent-a", Some(10), &entries).insert("host", twins_count);
+
+        assert_eq!(
+            cache("did:iotics:agent-a", Some(10), &entries).get("host"),
+            Some(twins_count)
+        );
+        assert_eq!(
+            cache("did:iotics:agent-b", Some(10), &entries).get("host"),
+            None
+        );
+        assert_eq!(
+            cache("did:iotics:agent-a", None, &entries).get("host"),
+            None
+        );
+    }
+}
@@ -2,32 +2,85 @@ use async_trait::async_trait;
 use std::str;
 use structopt::StructOpt;
 
+pub mod check;
+pub mod clone_model;
+pub mod color;
 pub mod delete_all_twins;
 pub mod delete_twins_by_model;
 pub mod describe_twin;
+pub mod describe_twins;
+pub mod dns;
+pub mod export;
+pub mod feed;
+mod feed_archive;
+mod feed_validation;
 pub mod follow_by_model;
+pub mod gc;
 mod helpers;
+pub mod host;
+pub mod import;
 pub mod list_hosts;
-mod settings;
+pub mod model;
+mod probe;
+mod rendering;
+pub mod replay;
+pub mod twin_selector;
 
 use self::{
+    check::CheckArgs, clone_model::CloneModelArgs, color::ColorChoice,
     delete_all_twins::DeleteAllTwinsArgs, delete_twins_by_model::DeleteTwinsByModelArgs,
-    describe_twin::DescribeTwinArgs, follow_by_model::FollowByModelArgs, list_hosts::ListHostsArgs,
+    describe_twin::DescribeTwinArgs, describe_twins::DescribeTwinsArgs, export::ExportArgs,
+    feed::FeedCommand, follow_by_model::FollowByModelArgs, gc::GcArgs, host::HostCommand,
+    import::ImportArgs, list_hosts::ListHostsArgs, model::ModelCommand, replay::ReplayArgs,
 };
 
 #[derive(Debug, StructOpt)]
 #[structopt(bin_name = "cargo")]
+pub struct Cli {
+    /// When to colour the output: `auto`, `always` or `never`. `auto` only colours a terminal, unless `NO_COLOR` is set
+    #[structopt(long, global = true, default_value = "auto")]
+    pub color: ColorChoice,
+    /// Don't print the configuration that's loaded
+    #[structopt(short, long, global = true)]
+    pub quiet: bool,
+    #[structopt(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, StructOpt)]
 pub enum Command {
     /// Deletes all twins that have been created from a given model
     DeleteTwinsByModel(DeleteTwinsByModelArgs),
     /// Deletes all twins from a host that have been created by the given identity
     DeleteAllTwins(DeleteAllTwinsArgs),
+    /// Deletes the twins of a host that are older or have been inactive for longer than given
+    Gc(GcArgs),
     /// Follow all twins that have been created from a given model
     FollowByModel(FollowByModelArgs),
     /// List all hosts that are found in the network
     ListHosts(ListHostsArgs),
     /// Describe Twin
     DescribeTwin(DescribeTwinArgs),
+    /// Describe many twins at once
+    DescribeTwins(DescribeTwinsArgs),
+    /// Export the full description of twins to YAML, JSON or NDJSON files
+    Export(ExportArgs),
+    /// Recreate the twins of an `export` archive, with new identities if they belong to another host or agent
+    Import(ImportArgs),
+    /// Copy a model and all the twins created from it to another host
+    CloneModel(CloneModelArgs),
+    /// Check the integrity of the twins created from models: orphans, unused models, missing properties and feeds, duplicate labels
+    Check(CheckArgs),
+    /// Feed related commands
+    Feed(FeedCommand),
+    /// Host related commands
+    #[structopt(alias = "hosts")]
+    Host(HostCommand),
+    /// Model related commands
+    #[structopt(alias = "models")]
+    Model(ModelCommand),
+    /// Share the samples recorded by `follow-by-model --record` to the given twins
+    Replay(ReplayArgs),
 }
 
 #[async_trait]
@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::describe_twin::write_twin_description;
+use crate::commands::rendering::{prefixed_uri, property_value, write_table};
+use crate::commands::RunnableCommand;
+use crate::operations::describe::fetch_twin_description;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::models::{instance_description, Instance};
+use crate::operations::twins::{find_twins_by_model, LABEL_PROPERTY};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct ModelDescribeArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// The model DID
+    #[structopt(short, long)]
+    pub model_did: String,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+pub struct ModelDescribe<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: ModelDescribeArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> ModelDescribe<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(
+        stdout: &'a mut W,
+        opts: ModelDescribeArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: ModelDescribeArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for ModelDescribe<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let model =
+            fetch_twin_description(self.host_api.clone(), &self.opts.model_did, None).await?;
+        let twins_dids = find_twins_by_model(self.host_api.clone(), &self.opts.model_did).await?;
+
+        writeln!(self.stdout)?;
+        write_twin_description(self.stdout, &model)?;
+
+        // what `model instantiate` gives every twin, but its label
+        let instance = instance_description(
+            &model,
+            "",
+            &Instance {
+                twin_key_name: "".to_string(),
+                label: "".to_string(),
+                properties: Vec::new(),
+            },
+        );
+        let rows = instance
+            .properties
+            .iter()
+            .filter(|property| property.key != LABEL_PROPERTY)
+            .map(|property| vec![prefixed_uri(&property.key), property_value(&property.value)])
+            .collect::<Vec<Vec<String>>>();
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "Inherited by instances ({}), along with the location, feeds and inputs",
+            Paint::yellow(rows.len())
+        )?;
+        write_table(self.stdout, "  ", &["Key", "Value"], &rows)?;
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "{} twins created from the model.",
+            Paint::yellow(twins_dids.len())
+        )?;
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+}
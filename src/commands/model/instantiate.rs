@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use iotics_grpc_client::common::{Property, StringLiteral, Value};
+
+use crate::commands::helpers::write_progress;
+use crate::commands::RunnableCommand;
+use crate::operations::describe::fetch_twin_description;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::models::{instantiate_model, Instance};
+use crate::settings::{AuthBuilder, Settings};
+
+/// A `key=value` property of the instances, the value being a template.
+#[derive(Debug, Clone)]
+pub struct PropertyTemplate {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for PropertyTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok(PropertyTemplate {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(anyhow::anyhow!("expected `key=value`")),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ModelInstantiateArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// The model DID
+    #[structopt(short, long)]
+    pub model_did: String,
+    /// The number of twins to create
+    #[structopt(long)]
+    pub count: usize,
+    /// The index of the first twin. Twins created again with the same index are updated
+    #[structopt(long, default_value = "0")]
+    pub start_index: usize,
+    /// The label of the twins. `{index}` is replaced by the index of the twin and `{label}` by the label of the model
+    #[structopt(long, default_value = "{label} {index}")]
+    pub label: String,
+    /// Optional. A string property of the twins, as `key=value`. The value is templated like the label. Can be repeated
+    #[structopt(long = "property")]
+    pub properties: Vec<PropertyTemplate>,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+fn render(template: &str, model_label: &str, index: usize) -> String {
+    template
+        .replace("{index}", &index.to_string())
+        .replace("{label}", model_label)
+}
+
+pub struct ModelInstantiate<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: ModelInstantiateArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> ModelInstantiate<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(
+        stdout: &'a mut W,
+        opts: ModelInstantiateArgs,
+        quiet: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: ModelInstantiateArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for ModelInstantiate<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let model =
+            fetch_twin_description(self.host_api.clone(), &self.opts.model_did, None).await?;
+        let model_label = model.label().unwrap_or_default();
+        let model_id = self.opts.model_did.rsplit(':').next().unwrap_or_default();
+
+        let instances = (self.opts.start_index..self.opts.start_index + self.opts.count)
+            .map(|index| Instance {
+                twin_key_name: format!("{}-instance-{}", model_id, index),
+                label: render(&self.opts.label, &model_label, index),
+                properties: self
+                    .opts
+                    .properties
+                    .iter()
+                    .map(|property| Property {
+                        key: property.key.clone(),
+                        value: Some(Value::StringLiteralValue(StringLiteral {
+                            value: render(&property.value, &model_label, index),
+                        })),
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<Instance>>();
+
+        writeln!(
+            self.stdout,
+            "Creating {} twins from model {}...",
+            Paint::yellow(instances.len()),
+            Paint::blue(&self.opts.model_did),
+        )?;
+        self.stdout.flush()?;
+
+        let mut twins_created = 0;
+        let mut failures = Vec::new();
+        let mut creations = Box::pin(instantiate_model(self.host_api.clone(), model, instances));
+        let mut index = 0;
+
+        while let Some((twin_did, result)) = creations.next().await {
+            write_progress(
+                self.stdout,
+                "Creating",
+                &twin_did,
+                &result,
+                index,
+                self.opts.verbose,
+            )?;
+            index += 1;
+
+            match result {
+                Ok(_) => twins_created += 1,
+                Err(e) => failures.push((twin_did, e)),
+            }
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "Created {} twins, {} failed.",
+            Paint::green(twins_created),
+            Paint::red(failures.len()),
+        )?;
+
+        for (twin_did, e) in &failures {
+            writeln!(self.stdout, "{} {:?}", twin_did, Paint::red(e))?;
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+        self.stdout.flush()?;
+
+        if !failures.is_empty() {
+            anyhow::bail!("{} twins failed to be created", failures.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::sync::Arc;
+use std::{io, marker};
+use structopt::StructOpt;
+use yansi::Paint;
+
+use crate::commands::rendering::{label, write_table};
+use crate::commands::RunnableCommand;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::operations::models::find_models;
+use crate::operations::twins::find_twins_by_model;
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct ModelListArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// The maximum number of models whose twins are counted at the same time
+    #[structopt(long, default_value = "8")]
+    pub concurrency: usize,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+pub struct ModelList<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: ModelListArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> ModelList<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: ModelListArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(
+        stdout: &'a mut W,
+        opts: ModelListArgs,
+        host_api: Arc<dyn HostApi>,
+    ) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for ModelList<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let models = find_models(self.host_api.as_ref()).await?;
+
+        let mut counts = stream::iter(models)
+            .map(|model| {
+                let host_api = self.host_api.clone();
+
+                async move {
+                    let result = find_twins_by_model(host_api, &model.id.value).await;
+                    (model, result)
+                }
+            })
+            .buffered(self.opts.concurrency.max(1));
+
+        let mut rows = Vec::new();
+        let mut instances_count = 0;
+
+        while let Some((model, result)) = counts.next().await {
+            let instances = match result {
+                Ok(twins_dids) => {
+                    instances_count += twins_dids.len();
+                    twins_dids.len().to_string()
+                }
+                Err(e) => format!("{}", e),
+            };
+
+            rows.push(vec![
+                model.id.value,
+                label(&model.properties).unwrap_or_default(),
+                instances,
+            ]);
+        }
+
+        write_table(self.stdout, "", &["DID", "Label", "Instances"], &rows)?;
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "Found {} models and {} twins created from them.",
+            Paint::yellow(rows.len()),
+            Paint::yellow(instances_count),
+        )?;
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+}
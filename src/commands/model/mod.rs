@@ -0,0 +1,17 @@
+use structopt::StructOpt;
+
+pub mod describe;
+pub mod instantiate;
+pub mod list;
+
+use self::{describe::ModelDescribeArgs, instantiate::ModelInstantiateArgs, list::ModelListArgs};
+
+#[derive(Debug, StructOpt)]
+pub enum ModelCommand {
+    /// List the models of the host, with the number of twins created from each of them
+    List(ModelListArgs),
+    /// Describe a model and what the twins created from it inherit
+    Describe(ModelDescribeArgs),
+    /// Create twins from a model, with templated labels and properties
+    Instantiate(ModelInstantiateArgs),
+}
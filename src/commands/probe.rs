@@ -0,0 +1,131 @@
+use std::future::Future;
+use std::time::Duration;
+
+use futures::stream::{Stream, StreamExt};
+use tokio::time::timeout;
+
+#[derive(Debug)]
+pub enum ProbeOutcome<T> {
+    Completed(T),
+    TimedOut,
+}
+
+/// Probes many hosts concurrently.
+///
+/// At most `concurrency` probes run at the same time. The inputs are only pulled when a slot is free,
+/// so a fast producer (e.g. a search stream) is slowed down rather than buffered.
+/// Every probe is spawned on its own task, so that a probe blocking its thread (e.g. minting a token)
+/// doesn't stall the others. It's given `timeout` from the moment it starts, and the results are yielded
+/// as they complete.
+#[derive(Debug, Clone, Copy)]
+pub struct ProbeEngine {
+    concurrency: usize,
+    timeout: Duration,
+}
+
+impl ProbeEngine {
+    pub fn new(concurrency: usize, timeout: Duration) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            timeout,
+        }
+    }
+
+    pub fn run<'a, I, T, S, F, Fut>(
+        &self,
+        inputs: S,
+        probe: F,
+    ) -> impl Stream<Item = (I, ProbeOutcome<T>)> + 'a
+    where
+        S: Stream<Item = I> + 'a,
+        I: Clone + 'a,
+        F: Fn(I) -> Fut + 'a,
+        Fut: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let probe_timeout = self.timeout;
+
+        inputs
+            .map(move |input| {
+                let mut probe_task = tokio::spawn(probe(input.clone()));
+
+                async move {
+                    match timeout(probe_timeout, &mut probe_task).await {
+                        Ok(Ok(output)) => (input, ProbeOutcome::Completed(output)),
+                        // a probe is only cancelled below, a panic is the probe's own
+                        Ok(Err(e)) => std::panic::resume_unwind(e.into_panic()),
+                        Err(_) => {
+                            probe_task.abort();
+                            (input, ProbeOutcome::TimedOut)
+                        }
+                    }
+                }
+            })
+            .buffer_unordered(self.concurrency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn a_blocking_probe_does_not_stall_the_others() {
+        let engine = ProbeEngine::new(4, Duration::from_secs(5));
+
+        let outcomes = engine
+            .run(stream::iter(0..4), |index| async move {
+                if index == 0 {
+                    std::thread::sleep(Duration::from_millis(500));
+                }
+                index
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<i32>>()
+            .await;
+
+        assert_eq!(outcomes.last(), Some(&0));
+    }
+
+    #[tokio::test]
+    async fn a_slow_probe_times_out() {
+        let engine = ProbeEngine::new(2, Duration::from_millis(50));
+
+        let outcomes = engine
+            .run(stream::iter([10, 1000]), |millis| async move {
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+            })
+            .collect::<Vec<(u64, ProbeOutcome<()>)>>()
+            .await;
+
+        assert!(matches!(outcomes[0], (10, ProbeOutcome::Completed(()))));
+        assert!(matches!(outcomes[1], (1000, ProbeOutcome::TimedOut)));
+    }
+
+    #[tokio::test]
+    async fn at_most_concurrency_probes_run_at_the_same_time() {
+        let engine = ProbeEngine::new(2, Duration::from_secs(5));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        engine
+            .run(stream::iter(0..8), |_| {
+                let running = running.clone();
+                let max_running = max_running.clone();
+
+                async move {
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now_running, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }
+            })
+            .collect::<Vec<(i32, ProbeOutcome<()>)>>()
+            .await;
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    }
+}
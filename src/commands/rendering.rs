@@ -0,0 +1,124 @@
+use std::io;
+use std::str::FromStr;
+
+use iotics_grpc_client::common::{Property, Value};
+
+use crate::operations::twins::LABEL_PROPERTY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "table" => Ok(OutputFormat::Table),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            _ => Err(anyhow::anyhow!("expected `table` or `ndjson`")),
+        }
+    }
+}
+
+/// Well-known prefixes used to shorten URIs when rendering them.
+const PREFIXES: &[(&str, &str)] = &[
+    ("rdf:", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs:", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("owl:", "http://www.w3.org/2002/07/owl#"),
+    ("xsd:", "http://www.w3.org/2001/XMLSchema#"),
+    ("skos:", "http://www.w3.org/2004/02/skos/core#"),
+    ("foaf:", "http://xmlns.com/foaf/0.1/"),
+    ("schema:", "http://schema.org/"),
+    ("iotics:", "http://data.iotics.com/iotics#"),
+    ("iotics-public:", "http://data.iotics.com/public#"),
+    ("iotics-app:", "https://data.iotics.com/app#"),
+];
+
+pub fn prefixed_uri(uri: &str) -> String {
+    for (prefix, namespace) in PREFIXES {
+        if let Some(name) = uri.strip_prefix(namespace) {
+            return format!("{}{}", prefix, name);
+        }
+    }
+
+    uri.to_string()
+}
+
+pub fn property_value(value: &Option<Value>) -> String {
+    match value {
+        Some(Value::UriValue(uri)) => prefixed_uri(&uri.value),
+        Some(Value::LiteralValue(literal)) if literal.data_type.is_empty() => {
+            format!("{:?}", literal.value)
+        }
+        Some(Value::LiteralValue(literal)) => format!(
+            "{:?}^^{}",
+            literal.value,
+            prefixed_uri(&format!(
+                "http://www.w3.org/2001/XMLSchema#{}",
+                literal.data_type
+            ))
+        ),
+        Some(Value::LangLiteralValue(lang_literal)) => {
+            format!("{:?}@{}", lang_literal.value, lang_literal.lang)
+        }
+        Some(Value::StringLiteralValue(string_literal)) => format!("{:?}", string_literal.value),
+        None => "".to_string(),
+    }
+}
+
+/// The label of a twin, without the language.
+pub fn label(properties: &[Property]) -> Option<String> {
+    properties
+        .iter()
+        .find(|property| property.key == LABEL_PROPERTY)
+        .map(|property| match &property.value {
+            Some(Value::LangLiteralValue(lang_literal)) => lang_literal.value.clone(),
+            Some(Value::StringLiteralValue(string_literal)) => string_literal.value.clone(),
+            value => property_value(value),
+        })
+}
+
+/// Writes rows as left-aligned columns, each as wide as its widest cell.
+pub fn write_table<W>(
+    stdout: &mut W,
+    indent: &str,
+    header: &[&str],
+    rows: &[Vec<String>],
+) -> Result<(), io::Error>
+where
+    W: io::Write + ?Sized,
+{
+    let mut widths = header.iter().map(|cell| cell.len()).collect::<Vec<usize>>();
+
+    for row in rows {
+        for (index, cell) in row.iter().enumerate() {
+            widths[index] = widths[index].max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<String>>()
+            .join("  ");
+
+        format!("{}{}", indent, line.trim_end())
+    };
+
+    writeln!(stdout, "{}", format_row(header.to_vec()))?;
+
+    for row in rows {
+        writeln!(
+            stdout,
+            "{}",
+            format_row(row.iter().map(|cell| cell.as_str()).collect())
+        )?;
+    }
+
+    Ok(())
+}
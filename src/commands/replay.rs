@@ -0,0 +1,177 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use std::{io, marker};
+use structopt::StructOpt;
+use tokio::time::{sleep_until, Instant};
+use yansi::Paint;
+
+use iotics_grpc_client::common::{FeedData, TwinId};
+
+use crate::commands::feed_archive::read_archive;
+use crate::commands::helpers::write_progress;
+use crate::commands::RunnableCommand;
+use crate::operations::host_api::{GrpcHostApi, HostApi};
+use crate::settings::{AuthBuilder, Settings};
+
+#[derive(Debug, StructOpt)]
+pub struct ReplayArgs {
+    /// Configuration file stored in the `configuration` folder. Don't include the extension.
+    #[structopt(short, long)]
+    pub config: String,
+    /// The archive recorded by `follow-by-model --record`
+    #[structopt(short, long)]
+    pub archive: PathBuf,
+    /// The DIDs of the twins to share the data to. They should be local to the given host in the configuration file.
+    /// The recorded twins are assigned to them in order of appearance, wrapping around if there are fewer targets.
+    #[structopt(long = "target-twin-did", required = true)]
+    pub target_twin_dids: Vec<String>,
+    /// Optional. The feed ID to share the data to. Defaults to the recorded feed ID
+    #[structopt(long)]
+    pub feed_id: Option<String>,
+    /// Speed multiplier applied to the recorded timing, e.g. 2 replays twice as fast. Between 0.001 and 1000
+    #[structopt(long, default_value = "1.0", parse(try_from_str = parse_speed))]
+    pub speed: f64,
+    /// Logging level
+    #[structopt(short, long)]
+    pub verbose: bool,
+}
+
+/// The bounds of the speed multiplier, beyond which the recorded timing can't be scaled.
+const MIN_SPEED: f64 = 0.001;
+const MAX_SPEED: f64 = 1000.0;
+
+fn parse_speed(value: &str) -> Result<f64, anyhow::Error> {
+    let speed = value
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("expected a number, e.g. `2` or `0.5`"))?;
+
+    // NaN fails both comparisons
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        anyhow::bail!(
+            "the speed multiplier must be between {} and {}",
+            MIN_SPEED,
+            MAX_SPEED
+        );
+    }
+
+    Ok(speed)
+}
+
+pub struct Replay<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    stdout: &'a mut W,
+    opts: ReplayArgs,
+    host_api: Arc<dyn HostApi>,
+}
+
+impl<'a, W> Replay<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    pub fn new(stdout: &'a mut W, opts: ReplayArgs, quiet: bool) -> Result<Self, anyhow::Error> {
+        let settings = Settings::new(&opts.config, quiet)?;
+        let host_api = GrpcHostApi::new(AuthBuilder::new(settings));
+        Ok(Self::with_host_api(stdout, opts, host_api))
+    }
+
+    /// Runs the command against the given host API, e.g. a `testing::MockHost`.
+    pub fn with_host_api(stdout: &'a mut W, opts: ReplayArgs, host_api: Arc<dyn HostApi>) -> Self {
+        Self {
+            stdout,
+            opts,
+            host_api,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, W> RunnableCommand for Replay<'a, W>
+where
+    W: io::Write + marker::Send,
+{
+    async fn run(self) -> Result<(), anyhow::Error> {
+        let records = read_archive(&self.opts.archive)?;
+
+        let mut targets: HashMap<String, String> = HashMap::new();
+
+        for record in &records {
+            if !targets.contains_key(&record.twin_did) {
+                let target =
+                    &self.opts.target_twin_dids[targets.len() % self.opts.target_twin_dids.len()];
+                targets.insert(record.twin_did.clone(), target.clone());
+
+                writeln!(
+                    self.stdout,
+                    "{} -> {}",
+                    Paint::blue(&record.twin_did),
+                    Paint::green(target),
+                )?;
+            }
+        }
+
+        writeln!(
+            self.stdout,
+            "Found {} samples from {} twins. Replaying...",
+            Paint::yellow(records.len()),
+            Paint::yellow(targets.len()),
+        )?;
+        self.stdout.flush()?;
+
+        let started_at = Instant::now();
+        let mut samples_shared = 0;
+
+        for (index, record) in records.into_iter().enumerate() {
+            let delay = Duration::from_millis(record.received_after_ms).div_f64(self.opts.speed);
+            sleep_until(started_at + delay).await;
+
+            let twin_did = targets
+                .get(&record.twin_did)
+                .expect("this should not happen")
+                .clone();
+            let feed_id = self.opts.feed_id.clone().unwrap_or(record.feed_id);
+            let target = format!("{} / {}", twin_did, feed_id);
+
+            let result = self
+                .host_api
+                .share_data(
+                    TwinId { value: twin_did },
+                    feed_id,
+                    FeedData {
+                        occurred_at: Some(SystemTime::now().into()),
+                        mime: record.mime,
+                        data: record.data,
+                    },
+                )
+                .await;
+
+            write_progress(
+                self.stdout,
+                "Sharing to",
+                &target,
+                &result,
+                index,
+                self.opts.verbose,
+            )?;
+
+            if result.is_ok() {
+                samples_shared += 1;
+            }
+        }
+
+        writeln!(self.stdout)?;
+        writeln!(
+            self.stdout,
+            "Shared {} samples.",
+            Paint::green(samples_shared)
+        )?;
+        writeln!(self.stdout)?;
+        writeln!(self.stdout, "Done.")?;
+
+        Ok(())
+    }
+}
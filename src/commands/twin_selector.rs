@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fs, io};
+use structopt::StructOpt;
+
+use crate::operations::host_api::HostApi;
+use crate::operations::twins::{find_twins_by_model, list_twins};
+
+/// The twins a command works on, given by DID, by model or all of them.
+#[derive(Debug, StructOpt)]
+pub struct TwinSelector {
+    /// The DIDs of the twins. Can be repeated
+    #[structopt(long = "twin-did")]
+    pub twin_dids: Vec<String>,
+    /// Optional. A file with one twin DID per line, `-` to read them from stdin
+    #[structopt(long)]
+    pub file: Option<PathBuf>,
+    /// Optional. Select all the twins that have been created from the given model
+    #[structopt(long)]
+    pub model_did: Option<String>,
+    /// Select all the twins that have been created by the given identity
+    #[structopt(long)]
+    pub all: bool,
+}
+
+fn read_twins_dids(file: &Path) -> Result<Vec<String>, anyhow::Error> {
+    let lines = if file.as_os_str() == "-" {
+        io::stdin()
+            .lock()
+            .lines()
+            .collect::<Result<Vec<String>, io::Error>>()?
+    } else {
+        fs::read_to_string(file)?
+            .lines()
+            .map(|line| line.to_string())
+            .collect()
+    };
+
+    Ok(lines
+        .into_iter()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect())
+}
+
+impl TwinSelector {
+    /// Returns the DIDs of the selected twins, each once and in order, failing if none was selected.
+    pub async fn select(&self, host_api: Arc<dyn HostApi>) -> Result<Vec<String>, anyhow::Error> {
+        let mut twins_dids = self.twin_dids.clone();
+
+        if let Some(file) = &self.file {
+            twins_dids.extend(read_twins_dids(file)?);
+        }
+
+        if let Some(model_did) = &self.model_did {
+            twins_dids.extend(find_twins_by_model(host_api.clone(), model_did).await?);
+        }
+
+        if self.all {
+            twins_dids.extend(list_twins(host_api).await?);
+        }
+
+        // a twin can be selected more than once, e.g. by DID and by model
+        let mut seen = HashSet::new();
+        twins_dids.retain(|twin_did| seen.insert(twin_did.clone()));
+
+        if twins_dids.is_empty() {
+            anyhow::bail!("no twins selected, use --twin-did, --file, --model-did or --all");
+        }
+
+        Ok(twins_dids)
+    }
+}
@@ -0,0 +1,14 @@
+//! The IOTICS CLI as a library.
+//!
+//! `settings` holds the configuration and the auth context of a host.
+//! `operations` holds typed operations on a host, returning results and streams.
+//! `commands` holds the CLI commands, rendering these operations to a writer.
+//! `testing`, behind the `testing` feature, holds the test doubles used to run both offline.
+
+pub mod commands;
+pub mod operations;
+pub mod settings;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use settings::{AuthBuilder, IoticsSettings, NetworkSettings, Settings};
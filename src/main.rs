@@ -1,33 +1,58 @@
+use env_logger::WriteStyle;
 use log::{error, LevelFilter};
 use std::io::stdout;
 use structopt::StructOpt;
 
-mod commands;
-
-use commands::delete_all_twins::DeleteAllTwins;
-use commands::delete_twins_by_model::DeleteTwinsByModel;
-use commands::describe_twin::DescribeTwin;
-use commands::follow_by_model::FollowByModel;
-use commands::list_hosts::ListHosts;
-use commands::{Command, RunnableCommand};
+use iotics_cli::commands::check::Check;
+use iotics_cli::commands::clone_model::CloneModel;
+use iotics_cli::commands::delete_all_twins::DeleteAllTwins;
+use iotics_cli::commands::delete_twins_by_model::DeleteTwinsByModel;
+use iotics_cli::commands::describe_twin::DescribeTwin;
+use iotics_cli::commands::describe_twins::DescribeTwins;
+use iotics_cli::commands::export::Export;
+use iotics_cli::commands::feed::last_value::FeedLastValue;
+use iotics_cli::commands::feed::FeedCommand;
+use iotics_cli::commands::follow_by_model::FollowByModel;
+use iotics_cli::commands::gc::Gc;
+use iotics_cli::commands::host::check::HostCheck;
+use iotics_cli::commands::host::diff::HostDiff;
+use iotics_cli::commands::host::stats::HostStats;
+use iotics_cli::commands::host::HostCommand;
+use iotics_cli::commands::import::Import;
+use iotics_cli::commands::list_hosts::ListHosts;
+use iotics_cli::commands::model::describe::ModelDescribe;
+use iotics_cli::commands::model::instantiate::ModelInstantiate;
+use iotics_cli::commands::model::list::ModelList;
+use iotics_cli::commands::model::ModelCommand;
+use iotics_cli::commands::replay::Replay;
+use iotics_cli::commands::{Cli, Command, RunnableCommand};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::from_args();
+
     let log_level = std::env::var("RUST_LOG")
         .unwrap_or_else(|_| "info".to_string())
         .parse()
         .unwrap_or(LevelFilter::Info);
 
+    let log_style = match cli.color.stderr_enabled() {
+        true => WriteStyle::Always,
+        false => WriteStyle::Never,
+    };
+
     pretty_env_logger::formatted_timed_builder()
         .filter(Some("iotics_"), log_level)
+        .write_style(log_style)
         .init();
 
+    cli.color.apply();
+
     let mut stdout = stdout();
-    let command = Command::from_args();
 
-    match command {
+    match cli.command {
         Command::DeleteTwinsByModel(args) => {
-            let command = DeleteTwinsByModel::new(&mut stdout, args);
+            let command = DeleteTwinsByModel::new(&mut stdout, args, cli.quiet);
 
             match command {
                 Ok(command) => {
@@ -43,7 +68,7 @@ async fn main() -> anyhow::Result<()> {
             }
         }
         Command::DeleteAllTwins(args) => {
-            let command = DeleteAllTwins::new(&mut stdout, args);
+            let command = DeleteAllTwins::new(&mut stdout, args, cli.quiet);
 
             match command {
                 Ok(command) => {
@@ -58,8 +83,27 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        Command::Gc(args) => {
+            let command = Gc::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                        // the twins that failed to be checked or deleted fail the command, e.g. in a scheduled job
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         Command::FollowByModel(args) => {
-            let command = FollowByModel::new(&mut stdout, args);
+            let command = FollowByModel::new(&mut stdout, args, cli.quiet);
 
             match command {
                 Ok(command) => {
@@ -75,7 +119,7 @@ async fn main() -> anyhow::Result<()> {
             }
         }
         Command::ListHosts(args) => {
-            let command = ListHosts::new(stdout, args);
+            let command = ListHosts::new(&mut stdout, args, cli.quiet);
 
             match command {
                 Ok(command) => {
@@ -91,7 +135,221 @@ async fn main() -> anyhow::Result<()> {
             }
         }
         Command::DescribeTwin(args) => {
-            let command = DescribeTwin::new(&mut stdout, args);
+            let command = DescribeTwin::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::DescribeTwins(args) => {
+            let command = DescribeTwins::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Export(args) => {
+            let command = Export::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Import(args) => {
+            let command = Import::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::CloneModel(args) => {
+            let command = CloneModel::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Feed(FeedCommand::LastValue(args)) => {
+            let command = FeedLastValue::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Check(args) => {
+            let command = Check::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                        // like `host check`, the issues found fail the command
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Host(HostCommand::Check(args)) => {
+            let command = HostCheck::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                        // a non-zero exit code lets the check be used as a CI smoke test
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Host(HostCommand::Diff(args)) => {
+            let command = HostDiff::new(&mut stdout, args);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Host(HostCommand::Stats(args)) => {
+            let command = HostStats::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Model(ModelCommand::List(args)) => {
+            let command = ModelList::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Model(ModelCommand::Describe(args)) => {
+            let command = ModelDescribe::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Model(ModelCommand::Instantiate(args)) => {
+            let command = ModelInstantiate::new(&mut stdout, args, cli.quiet);
+
+            match command {
+                Ok(command) => {
+                    let result = command.run().await;
+
+                    if let Err(e) = result {
+                        error!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                }
+            }
+        }
+        Command::Replay(args) => {
+            let command = Replay::new(&mut stdout, args, cli.quiet);
 
             match command {
                 Ok(command) => {
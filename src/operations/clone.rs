@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use iotics_grpc_client::common::TwinId;
+
+use crate::operations::describe::{fetch_twin_description, TwinDescription};
+use crate::operations::host_api::HostApi;
+use crate::operations::twins::find_twins_by_model;
+
+/// A model and the twins created from it.
+pub struct ModelTwins {
+    /// The model first
+    pub descriptions: Vec<TwinDescription>,
+    /// The twins that couldn't be described
+    pub failed: Vec<(String, anyhow::Error)>,
+}
+
+/// Describes the model and all the twins created from it.
+///
+/// Fails if the model can't be described, a twin that can't be described is reported and skipped.
+pub async fn describe_model_twins(
+    host_api: Arc<dyn HostApi>,
+    model_did: &str,
+) -> Result<ModelTwins, anyhow::Error> {
+    let model = fetch_twin_description(host_api.clone(), model_did, None).await?;
+    let twins_dids = find_twins_by_model(host_api.clone(), model_did).await?;
+
+    let mut model_twins = ModelTwins {
+        descriptions: vec![model],
+        failed: Vec::new(),
+    };
+
+    for twin_did in twins_dids {
+        match fetch_twin_description(host_api.clone(), &twin_did, None).await {
+            Ok(description) => model_twins.descriptions.push(description),
+            Err(e) => model_twins.failed.push((twin_did, e)),
+        }
+    }
+
+    Ok(model_twins)
+}
+
+/// Shares the last value stored on every feed of the source twin to the same feed of the destination twin.
+///
+/// The follower must be a twin of the source agent, local to the source host. Returns the number of values copied.
+pub async fn copy_last_values(
+    source: &dyn HostApi,
+    destination: &dyn HostApi,
+    description: &TwinDescription,
+    destination_twin_did: &str,
+    follower_twin_did: &str,
+) -> Result<usize, anyhow::Error> {
+    let mut values_copied = 0;
+
+    for feed in &description.feeds {
+        if feed.store_last != Some(true) {
+            continue;
+        }
+
+        let feed_data = source
+            .fetch_last_stored(
+                None,
+                TwinId {
+                    value: description.twin_did.clone(),
+                },
+                feed.id.clone(),
+                TwinId {
+                    value: follower_twin_did.to_string(),
+                },
+            )
+            .await?;
+
+        if let Some(feed_data) = feed_data {
+            destination
+                .share_data(
+                    TwinId {
+                        value: destination_twin_did.to_string(),
+                    },
+                    feed.id.clone(),
+                    feed_data,
+                )
+                .await?;
+
+            values_copied += 1;
+        }
+    }
+
+    Ok(values_copied)
+}
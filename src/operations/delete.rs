@@ -0,0 +1,63 @@
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+
+use crate::operations::host_api::HostApi;
+use crate::operations::twins::{find_twins_by_model, list_twins};
+
+/// The twins deleted and the ones that could not be.
+#[derive(Debug, Default)]
+pub struct DeletionReport {
+    pub deleted: Vec<String>,
+    pub failed: Vec<(String, anyhow::Error)>,
+}
+
+/// Deletes the twins one after the other, yielding the DID and the result of every deletion.
+pub fn delete_twins(
+    host_api: Arc<dyn HostApi>,
+    twins_dids: Vec<String>,
+) -> impl Stream<Item = (String, Result<(), anyhow::Error>)> {
+    stream::iter(twins_dids).then(move |twin_did| {
+        let host_api = host_api.clone();
+
+        async move {
+            let result = host_api.delete_twin(&twin_did).await;
+            (twin_did, result)
+        }
+    })
+}
+
+async fn delete_all(host_api: Arc<dyn HostApi>, twins_dids: Vec<String>) -> DeletionReport {
+    let mut report = DeletionReport::default();
+    let mut deletions = Box::pin(delete_twins(host_api, twins_dids));
+
+    while let Some((twin_did, result)) = deletions.next().await {
+        match result {
+            Ok(_) => report.deleted.push(twin_did),
+            Err(e) => report.failed.push((twin_did, e)),
+        }
+    }
+
+    report
+}
+
+/// Deletes all the twins created from the given model, and the model itself if `delete_model` is set.
+pub async fn delete_twins_by_model(
+    host_api: Arc<dyn HostApi>,
+    model_did: &str,
+    delete_model: bool,
+) -> Result<DeletionReport, anyhow::Error> {
+    let mut twins_dids = find_twins_by_model(host_api.clone(), model_did).await?;
+
+    if delete_model {
+        twins_dids.push(model_did.to_string());
+    }
+
+    Ok(delete_all(host_api, twins_dids).await)
+}
+
+/// Deletes all the twins of the host.
+pub async fn delete_all_twins(host_api: Arc<dyn HostApi>) -> Result<DeletionReport, anyhow::Error> {
+    let twins_dids = list_twins(host_api.clone()).await?;
+
+    Ok(delete_all(host_api, twins_dids).await)
+}
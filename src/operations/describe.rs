@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use iotics_grpc_client::common::{HostId, Property, TwinId, Value};
+
+use crate::operations::host_api::HostApi;
+use crate::operations::portable::PortableTwin;
+use crate::operations::twins::LABEL_PROPERTY;
+use crate::operations::value_definitions::ValueDefinition;
+
+/// A feed or an input of a twin.
+#[derive(Debug, Clone)]
+pub struct PointDescription {
+    pub id: String,
+    pub store_last: Option<bool>,
+    pub properties: Vec<Property>,
+    pub values: Vec<ValueDefinition>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TwinDescription {
+    pub twin_did: String,
+    pub host_id: Option<String>,
+    pub visibility: Option<String>,
+    pub location: Option<(f64, f64)>,
+    pub properties: Vec<Property>,
+    pub feeds: Vec<PointDescription>,
+    pub inputs: Vec<PointDescription>,
+}
+
+impl TwinDescription {
+    pub fn is_public(&self) -> bool {
+        self.visibility.as_deref() == Some("Public")
+    }
+
+    /// The label of the twin, without the language.
+    pub fn label(&self) -> Option<String> {
+        self.properties
+            .iter()
+            .find(|property| property.key == LABEL_PROPERTY)
+            .and_then(|property| match &property.value {
+                Some(Value::LangLiteralValue(lang_literal)) => Some(lang_literal.value.clone()),
+                Some(Value::StringLiteralValue(string_literal)) => {
+                    Some(string_literal.value.clone())
+                }
+                Some(Value::LiteralValue(literal)) => Some(literal.value.clone()),
+                Some(Value::UriValue(uri)) => Some(uri.value.clone()),
+                None => None,
+            })
+    }
+
+    /// The description as written by `export`.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(PortableTwin::from(self)).expect("this should not happen")
+    }
+}
+
+/// Describes a twin, along with the value definitions of all its feeds and inputs.
+pub async fn fetch_twin_description(
+    host_api: Arc<dyn HostApi>,
+    twin_did: &str,
+    host_id: Option<String>,
+) -> Result<TwinDescription, anyhow::Error> {
+    let remote_host_id = host_id.clone().map(|host_id| HostId { value: host_id });
+    let twin_id = TwinId {
+        value: twin_did.to_string(),
+    };
+
+    let twin_meta = host_api
+        .describe_twin(twin_id.clone(), remote_host_id.clone())
+        .await?;
+
+    let mut feeds = Vec::new();
+
+    for feed in twin_meta.feeds {
+        let values = host_api
+            .describe_feed(twin_id.clone(), &feed.id, remote_host_id.clone())
+            .await?;
+
+        feeds.push(PointDescription {
+            id: feed.id,
+            store_last: Some(feed.store_last),
+            properties: feed.properties,
+            values,
+        });
+    }
+
+    let mut inputs = Vec::new();
+
+    for input in twin_meta.inputs {
+        let values = host_api
+            .describe_input(twin_id.clone(), &input.id, remote_host_id.clone())
+            .await?;
+
+        inputs.push(PointDescription {
+            id: input.id,
+            store_last: None,
+            properties: input.properties,
+            values,
+        });
+    }
+
+    Ok(TwinDescription {
+        twin_did: twin_did.to_string(),
+        host_id,
+        visibility: twin_meta
+            .visibility
+            .map(|visibility| format!("{:?}", visibility)),
+        location: twin_meta.location,
+        properties: twin_meta.properties,
+        feeds,
+        inputs,
+    })
+}
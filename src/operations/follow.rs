@@ -0,0 +1,178 @@
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use futures::StreamExt;
+use std::sync::Arc;
+
+use iotics_grpc_client::common::{FeedData, HostId, Property, Scope, TwinId, Uri, Value};
+use iotics_grpc_client::search::Filter;
+
+use crate::operations::host_api::HostApi;
+use crate::operations::twins::MODEL_PROPERTY;
+use crate::operations::value_definitions::ValueDefinition;
+
+#[derive(Debug, Clone)]
+pub struct FollowByModelOptions {
+    pub model_did: String,
+    pub feed_id: String,
+    /// The follower twin, local to the host of the auth builder
+    pub follower_twin_did: String,
+    /// The maximum number of twins to follow
+    pub maximum_twins: usize,
+    /// Describe the feed of every twin before following it, e.g. to validate the data
+    pub describe_feed: bool,
+}
+
+/// What happens while following the twins of a model. `index` numbers the followed twins from 1.
+#[derive(Debug)]
+pub enum FollowEvent {
+    /// A search page came back with the given number of twins
+    Found {
+        host_id: Option<HostId>,
+        twins: usize,
+    },
+    /// The search page could not be read, the search goes on
+    SearchFailed(anyhow::Error),
+    Following {
+        index: usize,
+        twin_id: TwinId,
+        host_id: Option<HostId>,
+    },
+    /// The value definitions of the feed, before its first data. Only with `describe_feed`
+    Described {
+        index: usize,
+        twin_did: String,
+        values: Result<Vec<ValueDefinition>, anyhow::Error>,
+    },
+    Data {
+        index: usize,
+        twin_did: String,
+        feed_data: FeedData,
+    },
+    /// The follow stream of the twin could not be opened or broke, nothing more comes from that twin
+    Failed {
+        index: usize,
+        twin_did: String,
+        error: anyhow::Error,
+    },
+}
+
+/// Follows the feed of the twins created from the model, across the network.
+///
+/// The returned stream ends once the search is over and every follow stream has ended.
+pub async fn follow_by_model(
+    host_api: Arc<dyn HostApi>,
+    options: FollowByModelOptions,
+) -> Result<UnboundedReceiver<FollowEvent>, anyhow::Error> {
+    let filter = Filter {
+        properties: vec![Property {
+            key: MODEL_PROPERTY.to_string(),
+            value: Some(Value::UriValue(Uri {
+                value: options.model_did.clone(),
+            })),
+        }],
+        location: None,
+        text: None,
+    };
+
+    let mut pages = host_api.search(filter, Scope::Global, None).await?;
+    let follower_twin_id = TwinId {
+        value: options.follower_twin_did.clone(),
+    };
+
+    let (sender, receiver) = unbounded();
+
+    tokio::spawn(async move {
+        let mut count = 0;
+
+        while let Some(page) = pages.next().await {
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    let _ = sender.unbounded_send(FollowEvent::SearchFailed(e));
+                    continue;
+                }
+            };
+
+            let _ = sender.unbounded_send(FollowEvent::Found {
+                host_id: page.host_id.clone(),
+                twins: page.twins.len(),
+            });
+
+            for twin in page.twins {
+                count += 1;
+
+                let _ = sender.unbounded_send(FollowEvent::Following {
+                    index: count,
+                    twin_id: twin.id.clone(),
+                    host_id: page.host_id.clone(),
+                });
+
+                let host_api = host_api.clone();
+                let sender = sender.clone();
+                let host_id = page.host_id.clone();
+                let feed_id = options.feed_id.clone();
+                let follower_twin_id = follower_twin_id.clone();
+                let describe_feed = options.describe_feed;
+                let index = count;
+
+                // every twin is described and followed in its own task, not to hold the others
+                tokio::spawn(async move {
+                    let twin_did = twin.id.value.clone();
+
+                    if describe_feed {
+                        let values = host_api
+                            .describe_feed(twin.id.clone(), &feed_id, host_id.clone())
+                            .await;
+
+                        let event = FollowEvent::Described {
+                            index,
+                            twin_did: twin_did.clone(),
+                            values,
+                        };
+
+                        if sender.unbounded_send(event).is_err() {
+                            return;
+                        }
+                    }
+
+                    let feed_data = host_api
+                        .follow(host_id, twin.id, feed_id, follower_twin_id)
+                        .await;
+
+                    let error = match feed_data {
+                        Ok(mut feed_data) => loop {
+                            match feed_data.next().await {
+                                Some(Ok(feed_data)) => {
+                                    let event = FollowEvent::Data {
+                                        index,
+                                        twin_did: twin_did.clone(),
+                                        feed_data,
+                                    };
+
+                                    if sender.unbounded_send(event).is_err() {
+                                        // nobody is listening anymore
+                                        return;
+                                    }
+                                }
+                                Some(Err(e)) => break e,
+                                None => break anyhow::anyhow!("the follow stream ended"),
+                            }
+                        },
+                        Err(e) => e,
+                    };
+
+                    let _ = sender.unbounded_send(FollowEvent::Failed {
+                        index,
+                        twin_did,
+                        error,
+                    });
+                });
+
+                if count >= options.maximum_twins {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(receiver)
+}
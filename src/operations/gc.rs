@@ -0,0 +1,197 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::stream::{self, StreamExt};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use iotics_grpc_client::common::{Literal, Property, TwinId, Value};
+
+use crate::operations::host_api::HostApi;
+use crate::operations::twins::list_twins;
+
+pub const CREATED_AT_PROPERTY: &str = "https://data.iotics.com/app#createdAt";
+pub const UPDATED_AT_PROPERTY: &str = "https://data.iotics.com/app#updatedAt";
+
+/// When the feeds of a twin last shared data, as far as their stored values tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// None of the feeds stores its last value
+    Unknown,
+    /// The feeds storing their last value have none, the twin is as old as its last update
+    NeverShared,
+    LastShared(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwinAge {
+    pub twin_did: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub activity: Activity,
+}
+
+/// What makes a twin stale. A twin is stale if it matches all the criteria that are set.
+///
+/// A criterion is not matched if the twin doesn't tell, e.g. if it has no `createdAt` property.
+/// A twin that never shared is inactive, unless its `createdAt` or `updatedAt` property is within the period.
+#[derive(Debug, Clone, Default)]
+pub struct StaleCriteria {
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    pub inactive_since: Option<DateTime<Utc>>,
+}
+
+impl StaleCriteria {
+    pub fn is_empty(&self) -> bool {
+        self.created_before.is_none()
+            && self.updated_before.is_none()
+            && self.inactive_since.is_none()
+    }
+
+    pub fn matches(&self, age: &TwinAge) -> bool {
+        let before = |time: Option<DateTime<Utc>>, cutoff: Option<DateTime<Utc>>| match cutoff {
+            Some(cutoff) => matches!(time, Some(time) if time < cutoff),
+            None => true,
+        };
+
+        let inactive = match self.inactive_since {
+            Some(cutoff) => match age.activity {
+                Activity::Unknown => false,
+                // a twin that has just been created hasn't had the time to share anything, an undated one is old enough
+                Activity::NeverShared => match age.updated_at.max(age.created_at) {
+                    Some(last_changed_at) => last_changed_at < cutoff,
+                    None => true,
+                },
+                Activity::LastShared(last_shared_at) => last_shared_at < cutoff,
+            },
+            None => true,
+        };
+
+        !self.is_empty()
+            && before(age.created_at, self.created_before)
+            && before(age.updated_at, self.updated_before)
+            && inactive
+    }
+}
+
+/// The twins of the host that are stale, along with the ones that could not be checked.
+#[derive(Debug, Default)]
+pub struct StaleTwins {
+    pub checked: usize,
+    pub stale: Vec<TwinAge>,
+    pub failed: Vec<(String, anyhow::Error)>,
+}
+
+fn date_time(properties: &[Property], key: &str) -> Option<DateTime<Utc>> {
+    let value = properties
+        .iter()
+        .find(|property| property.key == key)
+        .and_then(|property| match &property.value {
+            Some(Value::LiteralValue(literal)) => Some(&literal.value),
+            Some(Value::StringLiteralValue(string_literal)) => Some(&string_literal.value),
+            _ => None,
+        })?;
+
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|date_time| date_time.with_timezone(&Utc))
+}
+
+/// Sets the `updatedAt` property of the twin to the time of the upsert, and its `createdAt` property too if it has none.
+pub fn stamp_upsert(properties: &mut Vec<Property>, upserted_at: DateTime<Utc>) {
+    let date_time_property = |key: &str| Property {
+        key: key.to_string(),
+        value: Some(Value::LiteralValue(Literal {
+            value: upserted_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            data_type: "dateTime".to_string(),
+        })),
+    };
+
+    if date_time(properties, CREATED_AT_PROPERTY).is_none() {
+        properties.retain(|property| property.key != CREATED_AT_PROPERTY);
+        properties.push(date_time_property(CREATED_AT_PROPERTY));
+    }
+
+    properties.retain(|property| property.key != UPDATED_AT_PROPERTY);
+    properties.push(date_time_property(UPDATED_AT_PROPERTY));
+}
+
+/// Reads the age of the twin from its properties, and its activity from the values stored on its feeds if `with_activity` is set.
+pub async fn fetch_twin_age(
+    host_api: &dyn HostApi,
+    twin_did: &str,
+    with_activity: bool,
+) -> Result<TwinAge, anyhow::Error> {
+    let twin_id = TwinId {
+        value: twin_did.to_string(),
+    };
+    let twin_meta = host_api.describe_twin(twin_id.clone(), None).await?;
+
+    let mut activity = Activity::Unknown;
+
+    if with_activity {
+        for feed in twin_meta.feeds.iter().filter(|feed| feed.store_last) {
+            // the twin follows its own feed
+            let feed_data = host_api
+                .fetch_last_stored(None, twin_id.clone(), feed.id.clone(), twin_id.clone())
+                .await?;
+
+            let shared_at = feed_data
+                .and_then(|feed_data| feed_data.occurred_at)
+                .and_then(|occurred_at| SystemTime::try_from(occurred_at).ok())
+                .map(DateTime::<Utc>::from);
+
+            activity = match (activity, shared_at) {
+                (Activity::LastShared(last_shared_at), Some(shared_at)) => {
+                    Activity::LastShared(last_shared_at.max(shared_at))
+                }
+                (Activity::LastShared(last_shared_at), None) => {
+                    Activity::LastShared(last_shared_at)
+                }
+                (_, Some(shared_at)) => Activity::LastShared(shared_at),
+                (_, None) => Activity::NeverShared,
+            };
+        }
+    }
+
+    Ok(TwinAge {
+        twin_did: twin_did.to_string(),
+        created_at: date_time(&twin_meta.properties, CREATED_AT_PROPERTY),
+        updated_at: date_time(&twin_meta.properties, UPDATED_AT_PROPERTY),
+        activity,
+    })
+}
+
+/// Checks all the twins of the host against the criteria, `concurrency` of them at the same time.
+pub async fn find_stale_twins(
+    host_api: Arc<dyn HostApi>,
+    criteria: &StaleCriteria,
+    concurrency: usize,
+) -> Result<StaleTwins, anyhow::Error> {
+    let twins_dids = list_twins(host_api.clone()).await?;
+    let with_activity = criteria.inactive_since.is_some();
+
+    let mut ages = stream::iter(twins_dids)
+        .map(|twin_did| {
+            let host_api = host_api.clone();
+
+            async move {
+                let result = fetch_twin_age(host_api.as_ref(), &twin_did, with_activity).await;
+                (twin_did, result)
+            }
+        })
+        .buffered(concurrency.max(1));
+
+    let mut stale_twins = StaleTwins::default();
+
+    while let Some((twin_did, result)) = ages.next().await {
+        stale_twins.checked += 1;
+
+        match result {
+            Ok(age) if criteria.matches(&age) => stale_twins.stale.push(age),
+            Ok(_) => {}
+            Err(e) => stale_twins.failed.push((twin_did, e)),
+        }
+    }
+
+    Ok(stale_twins)
+}
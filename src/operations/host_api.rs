@@ -0,0 +1,512 @@
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+
+use iotics_grpc_client::auth_builder::IntoAuthBuilder;
+use iotics_grpc_client::common::{
+    FeedData, FeedId, GeoLocation, HostId, InputId, Property, Scope, TwinId, Visibility,
+};
+use iotics_grpc_client::feed::{describe_feed, share_data};
+use iotics_grpc_client::input::describe_input;
+use iotics_grpc_client::interest::{
+    create_interest_api_client, fetch_last_stored, follow_with_client,
+};
+use iotics_grpc_client::search::{search, Filter};
+use iotics_grpc_client::twin::crud::{
+    delete_twin, upsert_twin, UpsertFeedWithMeta, UpsertInputWithMeta, ValueWithMeta,
+};
+use iotics_grpc_client::twin::describe::describe_twin;
+use iotics_grpc_client::twin::list::list_all_twins;
+
+use crate::operations::describe::TwinDescription;
+use crate::operations::value_definitions::ValueDefinition;
+use crate::settings::AuthBuilder;
+
+/// A page of search results, all from the same host.
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    /// `None` for the local host
+    pub host_id: Option<HostId>,
+    pub twins: Vec<FoundTwin>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FoundTwin {
+    pub id: TwinId,
+    pub visibility: Option<Visibility>,
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TwinSummary {
+    pub id: TwinId,
+    pub visibility: Option<Visibility>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedMeta {
+    pub id: String,
+    pub store_last: bool,
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InputMeta {
+    pub id: String,
+    pub properties: Vec<Property>,
+}
+
+/// What `describe_twin` returns, along with the raw response for debugging.
+#[derive(Debug, Clone)]
+pub struct TwinMeta {
+    pub visibility: Option<Visibility>,
+    pub location: Option<(f64, f64)>,
+    pub properties: Vec<Property>,
+    pub feeds: Vec<FeedMeta>,
+    pub inputs: Vec<InputMeta>,
+    pub raw: String,
+}
+
+/// The calls made to an IOTICS host.
+///
+/// `GrpcHostApi` talks to a real host. The operations and the commands only depend on this trait,
+/// so that they can be run against `testing::MockHost`.
+#[async_trait]
+pub trait HostApi: Send + Sync {
+    /// The gRPC URL of the host.
+    fn host_address(&self) -> String;
+
+    /// Derives the API of another host of the same network, with the same identity.
+    fn for_host(&self, host_address: String) -> Arc<dyn HostApi>;
+
+    /// Checks that the host accepts the identity with a single cheap authenticated call, the first page of a local search.
+    ///
+    /// The token of the auth builder is reused while it's valid.
+    async fn authenticate(&self) -> Result<(), anyhow::Error>;
+
+    /// Creates the identity of a twin controlled by the agent, returning its DID.
+    ///
+    /// The same key name always gives the same DID.
+    async fn create_twin_identity(&self, twin_key_name: &str) -> Result<String, anyhow::Error>;
+
+    /// Whether the agent controls the identity of the twin, i.e. can create or update the twin with its DID.
+    ///
+    /// The twin doesn't have to be on the host, e.g. after being deleted.
+    async fn controls_twin_identity(&self, twin_did: &str) -> Result<bool, anyhow::Error>;
+
+    /// Yields the search results page by page. Dropping the stream ends the search.
+    ///
+    /// Like a real search, the stream doesn't end on its own before the timeout.
+    async fn search(
+        &self,
+        filter: Filter,
+        scope: Scope,
+        timeout: Option<Duration>,
+    ) -> Result<BoxStream<'static, Result<SearchPage, anyhow::Error>>, anyhow::Error>;
+
+    async fn list_twins(&self) -> Result<Vec<TwinSummary>, anyhow::Error>;
+
+    async fn delete_twin(&self, twin_did: &str) -> Result<(), anyhow::Error>;
+
+    /// Creates the twin if it doesn't exist, then replaces its metadata, feeds and inputs with the description.
+    async fn upsert_twin(&self, description: &TwinDescription) -> Result<(), anyhow::Error>;
+
+    async fn describe_twin(
+        &self,
+        twin_id: TwinId,
+        host_id: Option<HostId>,
+    ) -> Result<TwinMeta, anyhow::Error>;
+
+    async fn describe_feed(
+        &self,
+        twin_id: TwinId,
+        feed_id: &str,
+        host_id: Option<HostId>,
+    ) -> Result<Vec<ValueDefinition>, anyhow::Error>;
+
+    async fn describe_input(
+        &self,
+        twin_id: TwinId,
+        input_id: &str,
+        host_id: Option<HostId>,
+    ) -> Result<Vec<ValueDefinition>, anyhow::Error>;
+
+    /// Yields the data shared on the feed until the stream breaks.
+    async fn follow(
+        &self,
+        host_id: Option<HostId>,
+        twin_id: TwinId,
+        feed_id: String,
+        follower_twin_id: TwinId,
+    ) -> Result<BoxStream<'static, Result<FeedData, anyhow::Error>>, anyhow::Error>;
+
+    async fn fetch_last_stored(
+        &self,
+        host_id: Option<HostId>,
+        twin_id: TwinId,
+        feed_id: String,
+        follower_twin_id: TwinId,
+    ) -> Result<Option<FeedData>, anyhow::Error>;
+
+    async fn share_data(
+        &self,
+        twin_id: TwinId,
+        feed_id: String,
+        feed_data: FeedData,
+    ) -> Result<(), anyhow::Error>;
+}
+
+/// How long `authenticate` waits for the host to answer.
+const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct GrpcHostApi {
+    auth_builder: Arc<AuthBuilder>,
+}
+
+impl GrpcHostApi {
+    pub fn new(auth_builder: Arc<AuthBuilder>) -> Arc<Self> {
+        Arc::new(Self { auth_builder })
+    }
+}
+
+#[async_trait]
+impl HostApi for GrpcHostApi {
+    fn host_address(&self) -> String {
+        self.auth_builder
+            .get_host()
+            .expect("failed to get host_address")
+    }
+
+    fn for_host(&self, host_address: String) -> Arc<dyn HostApi> {
+        GrpcHostApi::new(self.auth_builder.for_host(host_address))
+    }
+
+    async fn authenticate(&self) -> Result<(), anyhow::Error> {
+        // minting a token is a blocking call to the identity library
+        let auth_builder = self.auth_builder.clone();
+        tokio::task::spawn_blocking(move || auth_builder.get_token()).await??;
+
+        // only the host can tell whether it accepts the token, one page of results is enough
+        let filter = Filter {
+            properties: vec![],
+            location: None,
+            text: None,
+        };
+        let mut pages = self
+            .search(filter, Scope::Local, Some(AUTHENTICATION_TIMEOUT))
+            .await
+            .map_err(|e| anyhow::anyhow!("the host rejected the token: {}", e))?;
+
+        match pages.next().await {
+            Some(Ok(_)) => Ok(()),
+            Some(Err(e)) => Err(anyhow::anyhow!("the host rejected the token: {}", e)),
+            None => Err(anyhow::anyhow!(
+                "the host didn't answer the authenticated call"
+            )),
+        }
+    }
+
+    async fn create_twin_identity(&self, twin_key_name: &str) -> Result<String, anyhow::Error> {
+        // creating an identity is a blocking call to the identity library
+        let auth_builder = self.auth_builder.clone();
+        let twin_key_name = twin_key_name.to_string();
+
+        tokio::task::spawn_blocking(move || auth_builder.create_twin_did(&twin_key_name)).await?
+    }
+
+    async fn controls_twin_identity(&self, twin_did: &str) -> Result<bool, anyhow::Error> {
+        self.auth_builder.controls_twin_did(twin_did).await
+    }
+
+    async fn search(
+        &self,
+        filter: Filter,
+        scope: Scope,
+        timeout: Option<Duration>,
+    ) -> Result<BoxStream<'static, Result<SearchPage, anyhow::Error>>, anyhow::Error> {
+        let search_stream = search(self.auth_builder.clone(), filter, scope, timeout).await?;
+
+        let pages = stream::unfold(search_stream, |mut search_stream| async move {
+            let response = search_stream.recv().await?;
+
+            let page = response.map_err(anyhow::Error::from).map(|page| {
+                let payload = page.payload.unwrap_or_default();
+
+                SearchPage {
+                    host_id: payload.remote_host_id,
+                    twins: payload
+                        .twins
+                        .into_iter()
+                        .map(|twin| FoundTwin {
+                            id: twin.id.expect("this should not happen"),
+                            visibility: Visibility::from_i32(twin.visibility),
+                            properties: twin.properties,
+                        })
+                        .collect(),
+                }
+            });
+
+            Some((page, search_stream))
+        });
+
+        Ok(pages.boxed())
+    }
+
+    async fn list_twins(&self) -> Result<Vec<TwinSummary>, anyhow::Error> {
+        let twins = list_all_twins(self.auth_builder.clone()).await?;
+
+        Ok(twins
+            .into_iter()
+            .map(|twin| TwinSummary {
+                id: twin.id.expect("this should not happen"),
+                visibility: Visibility::from_i32(twin.visibility),
+            })
+            .collect())
+    }
+
+    async fn delete_twin(&self, twin_did: &str) -> Result<(), anyhow::Error> {
+        delete_twin(self.auth_builder.clone(), twin_did).await
+    }
+
+    async fn upsert_twin(&self, description: &TwinDescription) -> Result<(), anyhow::Error> {
+        let values_with_meta = |values: &[ValueDefinition]| {
+            values
+                .iter()
+                .map(|value| ValueWithMeta {
+                    label: value.label.clone(),
+                    data_type: value.data_type.clone(),
+                    unit: value.unit.clone(),
+                    comment: value.comment.clone(),
+                })
+                .collect::<Vec<ValueWithMeta>>()
+        };
+
+        let feeds = description
+            .feeds
+            .iter()
+            .map(|feed| UpsertFeedWithMeta {
+                id: feed.id.clone(),
+                store_last: feed.store_last.unwrap_or_default(),
+                properties: feed.properties.clone(),
+                values: values_with_meta(&feed.values),
+            })
+            .collect();
+
+        let inputs = description
+            .inputs
+            .iter()
+            .map(|input| UpsertInputWithMeta {
+                id: input.id.clone(),
+                properties: input.properties.clone(),
+                values: values_with_meta(&input.values),
+            })
+            .collect();
+
+        let visibility = match description.is_public() {
+            true => Visibility::Public,
+            false => Visibility::Private,
+        };
+
+        upsert_twin(
+            self.auth_builder.clone(),
+            &description.twin_did,
+            description.properties.clone(),
+            feeds,
+            inputs,
+            description
+                .location
+                .map(|(lat, lon)| GeoLocation { lat, lon }),
+            visibility,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn describe_twin(
+        &self,
+        twin_id: TwinId,
+        host_id: Option<HostId>,
+    ) -> Result<TwinMeta, anyhow::Error> {
+        let response = describe_twin(self.auth_builder.clone(), twin_id, host_id).await?;
+        let raw = format!("{:#?}", response);
+
+        let payload = response
+            .payload
+            .ok_or_else(|| anyhow::anyhow!("the describe response has no payload"))?;
+        let result = payload
+            .result
+            .ok_or_else(|| anyhow::anyhow!("the describe response has no result"))?;
+
+        Ok(TwinMeta {
+            visibility: payload
+                .twin
+                .and_then(|twin| Visibility::from_i32(twin.visibility)),
+            location: result.location.map(|location| (location.lat, location.lon)),
+            properties: result.properties,
+            feeds: result
+                .feeds
+                .into_iter()
+                .map(|feed| FeedMeta {
+                    id: feed.feed_id.expect("this should not happen").value,
+                    store_last: feed.store_last,
+                    properties: feed.properties,
+                })
+                .collect(),
+            inputs: result
+                .inputs
+                .into_iter()
+                .map(|input| InputMeta {
+                    id: input.input_id.expect("this should not happen").value,
+                    properties: input.properties,
+                })
+                .collect(),
+            raw,
+        })
+    }
+
+    async fn describe_feed(
+        &self,
+        twin_id: TwinId,
+        feed_id: &str,
+        host_id: Option<HostId>,
+    ) -> Result<Vec<ValueDefinition>, anyhow::Error> {
+        let response = describe_feed(
+            self.auth_builder.clone(),
+            twin_id,
+            FeedId {
+                value: feed_id.to_string(),
+            },
+            host_id,
+        )
+        .await?;
+
+        let values = response
+            .payload
+            .and_then(|payload| payload.result)
+            .map(|result| result.values)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|value| ValueDefinition {
+                label: value.label,
+                data_type: value.data_type,
+                unit: value.unit,
+                comment: value.comment,
+            })
+            .collect();
+
+        Ok(values)
+    }
+
+    async fn describe_input(
+        &self,
+        twin_id: TwinId,
+        input_id: &str,
+        host_id: Option<HostId>,
+    ) -> Result<Vec<ValueDefinition>, anyhow::Error> {
+        let response = describe_input(
+            self.auth_builder.clone(),
+            twin_id,
+            InputId {
+                value: input_id.to_string(),
+            },
+            host_id,
+        )
+        .await?;
+
+        let values = response
+            .payload
+            .and_then(|payload| payload.result)
+            .map(|result| result.values)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|value| ValueDefinition {
+                label: value.label,
+                data_type: value.data_type,
+                unit: value.unit,
+                comment: value.comment,
+            })
+            .collect();
+
+        Ok(values)
+    }
+
+    async fn follow(
+        &self,
+        host_id: Option<HostId>,
+        twin_id: TwinId,
+        feed_id: String,
+        follower_twin_id: TwinId,
+    ) -> Result<BoxStream<'static, Result<FeedData, anyhow::Error>>, anyhow::Error> {
+        let mut interest_channel = create_interest_api_client(self.auth_builder.clone()).await?;
+
+        let follow_stream = follow_with_client(
+            self.auth_builder.clone(),
+            &mut interest_channel,
+            host_id,
+            twin_id,
+            feed_id,
+            follower_twin_id,
+            true,
+        )
+        .await?;
+
+        let feed_data = stream::unfold(Some(follow_stream), |follow_stream| async move {
+            let mut follow_stream = follow_stream?;
+
+            loop {
+                match follow_stream.message().await {
+                    Ok(Some(result)) => {
+                        if let Some(feed_data) =
+                            result.payload.and_then(|payload| payload.feed_data)
+                        {
+                            return Some((Ok(feed_data), Some(follow_stream)));
+                        }
+                    }
+                    Ok(None) => return None,
+                    // nothing more comes after an error
+                    Err(e) => return Some((Err(e.into()), None)),
+                }
+            }
+        });
+
+        Ok(feed_data.boxed())
+    }
+
+    async fn fetch_last_stored(
+        &self,
+        host_id: Option<HostId>,
+        twin_id: TwinId,
+        feed_id: String,
+        follower_twin_id: TwinId,
+    ) -> Result<Option<FeedData>, anyhow::Error> {
+        let response = fetch_last_stored(
+            self.auth_builder.clone(),
+            host_id,
+            twin_id,
+            feed_id,
+            follower_twin_id,
+        )
+        .await?;
+
+        Ok(response.payload.and_then(|payload| payload.feed_data))
+    }
+
+    async fn share_data(
+        &self,
+        twin_id: TwinId,
+        feed_id: String,
+        feed_data: FeedData,
+    ) -> Result<(), anyhow::Error> {
+        share_data(
+            self.auth_builder.clone(),
+            twin_id,
+            FeedId { value: feed_id },
+            feed_data,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
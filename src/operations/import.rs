@@ -0,0 +1,104 @@
+use chrono::Utc;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use iotics_grpc_client::common::{Uri, Value};
+
+use crate::operations::describe::TwinDescription;
+use crate::operations::gc::stamp_upsert;
+use crate::operations::host_api::HostApi;
+
+/// The DID a twin of an archive gets on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidMapping {
+    pub original_did: String,
+    pub twin_did: String,
+    /// `false` if the twin keeps its DID
+    pub created: bool,
+}
+
+/// The key name of the identity created for an imported twin, the same on every import of the twin.
+pub fn import_key_name(original_did: &str) -> String {
+    let id = original_did.rsplit(':').next().unwrap_or(original_did);
+    format!("import-{}", id)
+}
+
+/// Maps every twin to the DID it gets on the host.
+///
+/// With `keep_controlled`, the twins whose identity the agent controls keep their DID, whether they're
+/// on the host or not, e.g. to restore them after a deletion. The others get a new identity under the agent.
+pub async fn map_twins_dids(
+    host_api: Arc<dyn HostApi>,
+    twins_dids: &[String],
+    keep_controlled: bool,
+) -> Result<Vec<DidMapping>, anyhow::Error> {
+    let mut mappings = Vec::new();
+
+    for original_did in twins_dids {
+        let keep_did = keep_controlled && host_api.controls_twin_identity(original_did).await?;
+
+        let mapping = match keep_did {
+            true => DidMapping {
+                original_did: original_did.clone(),
+                twin_did: original_did.clone(),
+                created: false,
+            },
+            false => DidMapping {
+                original_did: original_did.clone(),
+                twin_did: host_api
+                    .create_twin_identity(&import_key_name(original_did))
+                    .await?,
+                created: true,
+            },
+        };
+
+        mappings.push(mapping);
+    }
+
+    Ok(mappings)
+}
+
+/// Gives the twin its new DID and points the references to the other imported twins, e.g. its model, to their new DIDs.
+pub fn remap_twin(mut description: TwinDescription, mappings: &[DidMapping]) -> TwinDescription {
+    let twins_dids = mappings
+        .iter()
+        .map(|mapping| (mapping.original_did.as_str(), mapping.twin_did.as_str()))
+        .collect::<HashMap<&str, &str>>();
+
+    if let Some(twin_did) = twins_dids.get(description.twin_did.as_str()) {
+        description.twin_did = twin_did.to_string();
+    }
+
+    for property in &mut description.properties {
+        if let Some(Value::UriValue(uri)) = &property.value {
+            if let Some(twin_did) = twins_dids.get(uri.value.as_str()) {
+                property.value = Some(Value::UriValue(Uri {
+                    value: twin_did.to_string(),
+                }));
+            }
+        }
+    }
+
+    // the twins are recreated on the local host
+    description.host_id = None;
+    description
+}
+
+/// Upserts the twins one after the other, yielding the DID and the result of every upsert.
+///
+/// The twins are stamped as updated now, and created now unless the description tells otherwise.
+pub fn import_twins(
+    host_api: Arc<dyn HostApi>,
+    descriptions: Vec<TwinDescription>,
+) -> impl Stream<Item = (String, Result<(), anyhow::Error>)> {
+    stream::iter(descriptions).then(move |mut description| {
+        let host_api = host_api.clone();
+
+        async move {
+            stamp_upsert(&mut description.properties, Utc::now());
+            let result = host_api.upsert_twin(&description).await;
+            (description.twin_did, result)
+        }
+    })
+}
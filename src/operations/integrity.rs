@@ -0,0 +1,330 @@
+use futures::stream::{self, StreamExt};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use iotics_grpc_client::common::{Property, Scope, TwinId, Uri, Value};
+use iotics_grpc_client::search::Filter;
+
+use crate::operations::host_api::{FoundTwin, HostApi};
+use crate::operations::models::{is_inherited, MODEL_TYPE};
+use crate::operations::twins::{search_local_pages, LABEL_PROPERTY, MODEL_PROPERTY, TYPE_PROPERTY};
+
+/// A problem found on the twins of a host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// The model of the twin is neither on the host nor found on another host of the network
+    OrphanTwin { twin_did: String, model_did: String },
+    /// No twin has been created from the model
+    UnusedModel { model_did: String },
+    /// The twin doesn't have a property its model gives to its instances
+    MissingProperty {
+        twin_did: String,
+        model_did: String,
+        key: String,
+    },
+    /// The twin doesn't have a feed of its model
+    MissingFeed {
+        twin_did: String,
+        model_did: String,
+        feed_id: String,
+    },
+    /// More than one twin has the label
+    DuplicateLabel {
+        label: String,
+        twins_dids: Vec<String>,
+    },
+}
+
+impl IntegrityIssue {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IntegrityIssue::OrphanTwin { .. } => "orphan twin",
+            IntegrityIssue::UnusedModel { .. } => "unused model",
+            IntegrityIssue::MissingProperty { .. } => "missing property",
+            IntegrityIssue::MissingFeed { .. } => "missing feed",
+            IntegrityIssue::DuplicateLabel { .. } => "duplicate label",
+        }
+    }
+}
+
+fn uri_value<'a>(properties: &'a [Property], key: &str) -> Option<&'a str> {
+    properties
+        .iter()
+        .find(|property| property.key == key)
+        .and_then(|property| match &property.value {
+            Some(Value::UriValue(uri)) => Some(uri.value.as_str()),
+            _ => None,
+        })
+}
+
+fn is_model(twin: &FoundTwin) -> bool {
+    twin.properties.iter().any(|property| {
+        property.key == TYPE_PROPERTY
+            && matches!(&property.value, Some(Value::UriValue(uri)) if uri.value == MODEL_TYPE)
+    })
+}
+
+fn label(twin: &FoundTwin) -> Option<&str> {
+    twin.properties
+        .iter()
+        .find(|property| property.key == LABEL_PROPERTY)
+        .and_then(|property| match &property.value {
+            Some(Value::LangLiteralValue(lang_literal)) => Some(lang_literal.value.as_str()),
+            Some(Value::StringLiteralValue(string_literal)) => Some(string_literal.value.as_str()),
+            Some(Value::LiteralValue(literal)) => Some(literal.value.as_str()),
+            _ => None,
+        })
+}
+
+/// How long the network is searched for the models that are not on the host.
+const REMOTE_MODELS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether the host answered that the twin doesn't exist, as opposed to failing to answer.
+fn is_not_found(e: &anyhow::Error) -> bool {
+    let message = format!("{:#}", e).to_lowercase();
+    message.contains("not found") || message.contains("notfound")
+}
+
+/// Returns the models that are nowhere: not on the host, whatever their type, nor found on another host.
+///
+/// Fails if the host doesn't answer, rather than taking a model for missing.
+async fn find_missing_models(
+    host_api: Arc<dyn HostApi>,
+    models_dids: BTreeSet<String>,
+    concurrency: usize,
+) -> Result<HashSet<String>, anyhow::Error> {
+    let described = stream::iter(models_dids)
+        .map(|model_did| {
+            let host_api = host_api.clone();
+
+            async move {
+                let twin_id = TwinId {
+                    value: model_did.clone(),
+                };
+
+                match host_api.describe_twin(twin_id, None).await {
+                    Ok(_) => Ok((model_did, true)),
+                    Err(e) if is_not_found(&e) => Ok((model_did, false)),
+                    Err(e) => Err(e),
+                }
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect::<Vec<Result<(String, bool), anyhow::Error>>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<(String, bool)>, anyhow::Error>>()?;
+
+    let mut missing_models = described
+        .into_iter()
+        .filter(|(_, on_host)| !on_host)
+        .map(|(model_did, _)| model_did)
+        .collect::<HashSet<String>>();
+
+    if missing_models.is_empty() {
+        return Ok(missing_models);
+    }
+
+    let filter = Filter {
+        properties: vec![Property {
+            key: TYPE_PROPERTY.to_string(),
+            value: Some(Value::UriValue(Uri {
+                value: MODEL_TYPE.to_string(),
+            })),
+        }],
+        location: None,
+        text: None,
+    };
+    let mut pages = host_api
+        .search(filter, Scope::Global, Some(REMOTE_MODELS_TIMEOUT))
+        .await?;
+
+    while let Some(page) = pages.next().await {
+        for twin in page?.twins {
+            missing_models.remove(&twin.id.value);
+        }
+    }
+
+    Ok(missing_models)
+}
+
+/// Fetches the IDs of the feeds of every twin.
+async fn fetch_feeds_ids(
+    host_api: Arc<dyn HostApi>,
+    twins_dids: Vec<String>,
+    concurrency: usize,
+) -> Result<HashMap<String, HashSet<String>>, anyhow::Error> {
+    stream::iter(twins_dids)
+        .map(|twin_did| {
+            let host_api = host_api.clone();
+
+            async move {
+                let twin_meta = host_api
+                    .describe_twin(
+                        TwinId {
+                            value: twin_did.clone(),
+                        },
+                        None,
+                    )
+                    .await?;
+                let feeds_ids = twin_meta.feeds.into_iter().map(|feed| feed.id).collect();
+
+                Ok((twin_did, feeds_ids))
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect::<Vec<Result<(String, HashSet<String>), anyhow::Error>>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Scans all the twins of the local host for integrity problems.
+///
+/// The twins are compared to their model when it's on the same host. The twins created from a model are described,
+/// along with the model, to compare their feeds; `concurrency` of them at the same time. A twin is only an orphan
+/// if its model can't be described on the host nor found on the network.
+pub async fn check_integrity(
+    host_api: Arc<dyn HostApi>,
+    concurrency: usize,
+) -> Result<Vec<IntegrityIssue>, anyhow::Error> {
+    let pages = search_local_pages(
+        host_api.as_ref(),
+        Filter {
+            properties: vec![],
+            location: None,
+            text: None,
+        },
+    )
+    .await?;
+
+    let mut twins = Vec::new();
+    let mut pages = Box::pin(pages);
+
+    while let Some(page) = pages.next().await {
+        twins.extend(page?.twins);
+    }
+
+    let models = twins
+        .iter()
+        .filter(|twin| is_model(twin))
+        .map(|twin| (twin.id.value.as_str(), twin))
+        .collect::<BTreeMap<&str, &FoundTwin>>();
+
+    let mut issues = Vec::new();
+    let mut instances = Vec::new();
+    let mut used_models = HashSet::new();
+    // the twins whose model isn't among the models of the host
+    let mut elsewhere = Vec::new();
+
+    for twin in &twins {
+        let model_did = match uri_value(&twin.properties, MODEL_PROPERTY) {
+            Some(model_did) => model_did,
+            None => continue,
+        };
+
+        used_models.insert(model_did);
+
+        match models.get(model_did) {
+            Some(model) => instances.push((twin, *model)),
+            None => elsewhere.push((twin, model_did)),
+        }
+    }
+
+    let missing_models = find_missing_models(
+        host_api.clone(),
+        elsewhere
+            .iter()
+            .map(|(_, model_did)| model_did.to_string())
+            .collect(),
+        concurrency,
+    )
+    .await?;
+
+    for (twin, model_did) in elsewhere {
+        if missing_models.contains(model_did) {
+            issues.push(IntegrityIssue::OrphanTwin {
+                twin_did: twin.id.value.clone(),
+                model_did: model_did.to_string(),
+            });
+        }
+    }
+
+    for model_did in models.keys() {
+        if !used_models.contains(model_did) {
+            issues.push(IntegrityIssue::UnusedModel {
+                model_did: model_did.to_string(),
+            });
+        }
+    }
+
+    for (twin, model) in &instances {
+        let keys = twin
+            .properties
+            .iter()
+            .map(|property| property.key.as_str())
+            .collect::<HashSet<&str>>();
+        let mut missing_keys = Vec::new();
+
+        for property in model
+            .properties
+            .iter()
+            .filter(|property| is_inherited(property))
+        {
+            if !keys.contains(property.key.as_str()) && !missing_keys.contains(&property.key) {
+                missing_keys.push(property.key.clone());
+            }
+        }
+
+        for key in missing_keys {
+            issues.push(IntegrityIssue::MissingProperty {
+                twin_did: twin.id.value.clone(),
+                model_did: model.id.value.clone(),
+                key,
+            });
+        }
+    }
+
+    let mut described = models
+        .keys()
+        .map(|model_did| model_did.to_string())
+        .collect::<Vec<String>>();
+    described.extend(instances.iter().map(|(twin, _)| twin.id.value.clone()));
+    let feeds_ids = fetch_feeds_ids(host_api, described, concurrency).await?;
+
+    for (twin, model) in &instances {
+        let twin_feeds_ids = &feeds_ids[&twin.id.value];
+        let mut model_feeds_ids = feeds_ids[&model.id.value].iter().collect::<Vec<&String>>();
+        model_feeds_ids.sort();
+
+        for feed_id in model_feeds_ids {
+            if !twin_feeds_ids.contains(feed_id) {
+                issues.push(IntegrityIssue::MissingFeed {
+                    twin_did: twin.id.value.clone(),
+                    model_did: model.id.value.clone(),
+                    feed_id: feed_id.clone(),
+                });
+            }
+        }
+    }
+
+    let mut labels = BTreeMap::<&str, Vec<String>>::new();
+
+    for twin in &twins {
+        if let Some(label) = label(twin) {
+            labels.entry(label).or_default().push(twin.id.value.clone());
+        }
+    }
+
+    for (label, twins_dids) in labels {
+        if twins_dids.len() > 1 {
+            issues.push(IntegrityIssue::DuplicateLabel {
+                label: label.to_string(),
+                twins_dids,
+            });
+        }
+    }
+
+    Ok(issues)
+}
@@ -0,0 +1,16 @@
+//! Operations on an IOTICS host that don't write anything, to be used from other Rust services.
+//!
+//! Every operation takes a `HostApi`, usually a `GrpcHostApi` built from an `AuthBuilder`.
+
+pub mod clone;
+pub mod delete;
+pub mod describe;
+pub mod follow;
+pub mod gc;
+pub mod host_api;
+pub mod import;
+pub mod integrity;
+pub mod models;
+pub mod portable;
+pub mod twins;
+pub mod value_definitions;
@@ -0,0 +1,166 @@
+use chrono::Utc;
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+
+use iotics_grpc_client::common::{LangLiteral, Property, TwinId, Uri, Value};
+use iotics_grpc_client::search::Filter;
+
+use crate::operations::describe::TwinDescription;
+use crate::operations::gc::{stamp_upsert, CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY};
+use crate::operations::host_api::{FoundTwin, HostApi};
+use crate::operations::twins::{search_local_pages, LABEL_PROPERTY, MODEL_PROPERTY, TYPE_PROPERTY};
+
+pub const MODEL_TYPE: &str = "https://data.iotics.com/app#Model";
+/// The type of the instances of a model
+pub const DEFINES_PROPERTY: &str = "https://data.iotics.com/app#defines";
+
+/// The properties that describe the model itself, none of them is given to the instances.
+const MODEL_ONLY_PROPERTIES: &[&str] = &[
+    TYPE_PROPERTY,
+    LABEL_PROPERTY,
+    DEFINES_PROPERTY,
+    MODEL_PROPERTY,
+    "http://www.w3.org/2000/01/rdf-schema#comment",
+    CREATED_AT_PROPERTY,
+    UPDATED_AT_PROPERTY,
+];
+
+/// A twin to create from a model.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// The identity of the twin is created from the key name, an instance keeps its DID when created again
+    pub twin_key_name: String,
+    pub label: String,
+    /// Added to the properties inherited from the model, replacing the inherited ones with the same key
+    pub properties: Vec<Property>,
+}
+
+/// Searches the local host for the twins of type model.
+pub async fn find_models(host_api: &dyn HostApi) -> Result<Vec<FoundTwin>, anyhow::Error> {
+    let filter = Filter {
+        properties: vec![Property {
+            key: TYPE_PROPERTY.to_string(),
+            value: Some(Value::UriValue(Uri {
+                value: MODEL_TYPE.to_string(),
+            })),
+        }],
+        location: None,
+        text: None,
+    };
+
+    let pages = search_local_pages(host_api, filter).await?;
+    let pages = pages
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    Ok(pages.into_iter().flat_map(|page| page.twins).collect())
+}
+
+/// Whether the instances of a model get the property of the model.
+pub fn is_inherited(property: &Property) -> bool {
+    !MODEL_ONLY_PROPERTIES.contains(&property.key.as_str())
+}
+
+/// Describes a twin created from the model: it inherits the properties, the location, the feeds and the inputs of the model.
+pub fn instance_description(
+    model: &TwinDescription,
+    twin_did: &str,
+    instance: &Instance,
+) -> TwinDescription {
+    let mut properties = vec![
+        Property {
+            key: MODEL_PROPERTY.to_string(),
+            value: Some(Value::UriValue(Uri {
+                value: model.twin_did.clone(),
+            })),
+        },
+        Property {
+            key: LABEL_PROPERTY.to_string(),
+            value: Some(Value::LangLiteralValue(LangLiteral {
+                value: instance.label.clone(),
+                lang: "en".to_string(),
+            })),
+        },
+    ];
+
+    // the model defines the type of its instances
+    for property in &model.properties {
+        if property.key == DEFINES_PROPERTY {
+            properties.push(Property {
+                key: TYPE_PROPERTY.to_string(),
+                value: property.value.clone(),
+            });
+        }
+    }
+
+    let is_overridden = |property: &Property| {
+        instance
+            .properties
+            .iter()
+            .any(|instance_property| instance_property.key == property.key)
+    };
+
+    properties.extend(
+        model
+            .properties
+            .iter()
+            .filter(|property| is_inherited(property) && !is_overridden(property))
+            .cloned(),
+    );
+    properties.extend(instance.properties.iter().cloned());
+
+    TwinDescription {
+        twin_did: twin_did.to_string(),
+        host_id: None,
+        visibility: model.visibility.clone(),
+        location: model.location,
+        properties,
+        feeds: model.feeds.clone(),
+        inputs: model.inputs.clone(),
+    }
+}
+
+/// Creates the instances one after the other, yielding the DID and the result of every creation.
+///
+/// The key name stands in for the DID if the identity could not be created.
+pub fn instantiate_model(
+    host_api: Arc<dyn HostApi>,
+    model: TwinDescription,
+    instances: Vec<Instance>,
+) -> impl Stream<Item = (String, Result<(), anyhow::Error>)> {
+    let model = Arc::new(model);
+
+    stream::iter(instances).then(move |instance| {
+        let host_api = host_api.clone();
+        let model = model.clone();
+
+        async move {
+            let twin_did = match host_api.create_twin_identity(&instance.twin_key_name).await {
+                Ok(twin_did) => twin_did,
+                Err(e) => return (instance.twin_key_name, Err(e)),
+            };
+
+            let mut description = instance_description(&model, &twin_did, &instance);
+
+            // an instance created again keeps its creation time
+            let twin_id = TwinId {
+                value: twin_did.clone(),
+            };
+            if let Ok(twin_meta) = host_api.describe_twin(twin_id, None).await {
+                description.properties.extend(
+                    twin_meta
+                        .properties
+                        .into_iter()
+                        .filter(|property| property.key == CREATED_AT_PROPERTY),
+                );
+            }
+            stamp_upsert(&mut description.properties, Utc::now());
+
+            let result = host_api.upsert_twin(&description).await;
+
+            (twin_did, result)
+        }
+    })
+}
@@ -0,0 +1,215 @@
+//! A serializable form of a twin description, written by `describe-twins --output ndjson` and `export`
+//! and read back by `import`.
+
+use serde::{Deserialize, Serialize};
+
+use iotics_grpc_client::common::{LangLiteral, Literal, Property, StringLiteral, Uri, Value};
+
+use crate::operations::describe::{PointDescription, TwinDescription};
+use crate::operations::value_definitions::ValueDefinition;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableTwin {
+    pub twin_did: String,
+    pub host_id: Option<String>,
+    pub visibility: Option<String>,
+    pub location: Option<PortableLocation>,
+    pub properties: Vec<PortableProperty>,
+    pub feeds: Vec<PortablePoint>,
+    pub inputs: Vec<PortablePoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortableLocation {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortableProperty {
+    pub key: String,
+    pub value: Option<PortableValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PortableValue {
+    Uri {
+        uri: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Literal {
+        literal: String,
+        data_type: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    LangLiteral {
+        lang_literal: String,
+        lang: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    StringLiteral {
+        string_literal: String,
+    },
+}
+
+/// A feed or an input, `storeLast` is only set for feeds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortablePoint {
+    pub id: String,
+    pub store_last: Option<bool>,
+    /// Missing from the files exported before the properties of the points were kept
+    #[serde(default)]
+    pub properties: Vec<PortableProperty>,
+    pub values: Vec<PortableValueDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableValueDefinition {
+    pub label: String,
+    pub data_type: String,
+    pub unit: String,
+    pub comment: String,
+}
+
+impl From<&Value> for PortableValue {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::UriValue(uri) => PortableValue::Uri {
+                uri: uri.value.clone(),
+            },
+            Value::LiteralValue(literal) => PortableValue::Literal {
+                literal: literal.value.clone(),
+                data_type: literal.data_type.clone(),
+            },
+            Value::LangLiteralValue(lang_literal) => PortableValue::LangLiteral {
+                lang_literal: lang_literal.value.clone(),
+                lang: lang_literal.lang.clone(),
+            },
+            Value::StringLiteralValue(string_literal) => PortableValue::StringLiteral {
+                string_literal: string_literal.value.clone(),
+            },
+        }
+    }
+}
+
+impl From<&Property> for PortableProperty {
+    fn from(property: &Property) -> Self {
+        Self {
+            key: property.key.clone(),
+            value: property.value.as_ref().map(Into::into),
+        }
+    }
+}
+
+impl From<&ValueDefinition> for PortableValueDefinition {
+    fn from(value: &ValueDefinition) -> Self {
+        Self {
+            label: value.label.clone(),
+            data_type: value.data_type.clone(),
+            unit: value.unit.clone(),
+            comment: value.comment.clone(),
+        }
+    }
+}
+
+impl From<&PointDescription> for PortablePoint {
+    fn from(point: &PointDescription) -> Self {
+        Self {
+            id: point.id.clone(),
+            store_last: point.store_last,
+            properties: point.properties.iter().map(Into::into).collect(),
+            values: point.values.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&TwinDescription> for PortableTwin {
+    fn from(description: &TwinDescription) -> Self {
+        Self {
+            twin_did: description.twin_did.clone(),
+            host_id: description.host_id.clone(),
+            visibility: description.visibility.clone(),
+            location: description
+                .location
+                .map(|(lat, lon)| PortableLocation { lat, lon }),
+            properties: description.properties.iter().map(Into::into).collect(),
+            feeds: description.feeds.iter().map(Into::into).collect(),
+            inputs: description.inputs.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&PortableValue> for Value {
+    fn from(value: &PortableValue) -> Self {
+        match value {
+            PortableValue::Uri { uri } => Value::UriValue(Uri { value: uri.clone() }),
+            PortableValue::Literal { literal, data_type } => Value::LiteralValue(Literal {
+                value: literal.clone(),
+                data_type: data_type.clone(),
+            }),
+            PortableValue::LangLiteral { lang_literal, lang } => {
+                Value::LangLiteralValue(LangLiteral {
+                    value: lang_literal.clone(),
+                    lang: lang.clone(),
+                })
+            }
+            PortableValue::StringLiteral { string_literal } => {
+                Value::StringLiteralValue(StringLiteral {
+                    value: string_literal.clone(),
+                })
+            }
+        }
+    }
+}
+
+impl From<&PortableProperty> for Property {
+    fn from(property: &PortableProperty) -> Self {
+        Self {
+            key: property.key.clone(),
+            value: property.value.as_ref().map(Into::into),
+        }
+    }
+}
+
+impl From<&PortableValueDefinition> for ValueDefinition {
+    fn from(value: &PortableValueDefinition) -> Self {
+        Self {
+            label: value.label.clone(),
+            data_type: value.data_type.clone(),
+            unit: value.unit.clone(),
+            comment: value.comment.clone(),
+        }
+    }
+}
+
+impl From<&PortablePoint> for PointDescription {
+    fn from(point: &PortablePoint) -> Self {
+        Self {
+            id: point.id.clone(),
+            store_last: point.store_last,
+            properties: point.properties.iter().map(Into::into).collect(),
+            values: point.values.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&PortableTwin> for TwinDescription {
+    fn from(twin: &PortableTwin) -> Self {
+        Self {
+            twin_did: twin.twin_did.clone(),
+            host_id: twin.host_id.clone(),
+            visibility: twin.visibility.clone(),
+            location: twin
+                .location
+                .as_ref()
+                .map(|location| (location.lat, location.lon)),
+            properties: twin.properties.iter().map(Into::into).collect(),
+            feeds: twin.feeds.iter().map(Into::into).collect(),
+            inputs: twin.inputs.iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use iotics_grpc_client::common::{Property, Scope, Uri, Value};
+use iotics_grpc_client::search::Filter;
+use iotics_grpc_client::twin::PAGE_SIZE;
+
+use crate::operations::host_api::{HostApi, SearchPage};
+
+pub const MODEL_PROPERTY: &str = "https://data.iotics.com/app#model";
+pub const TYPE_PROPERTY: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+pub const LABEL_PROPERTY: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+
+/// How long to wait for the page following a full one before considering the search over.
+const NEXT_PAGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Searches the local host, yielding every page until the first one that's not full.
+///
+/// The search only ends on timeout: after a full page, the search is over if no other page comes
+/// within `NEXT_PAGE_TIMEOUT`, e.g. when the host has an exact multiple of `PAGE_SIZE` twins.
+pub async fn search_local_pages(
+    host_api: &dyn HostApi,
+    filter: Filter,
+) -> Result<impl Stream<Item = Result<SearchPage, anyhow::Error>>, anyhow::Error> {
+    let pages = host_api.search(filter, Scope::Local, None).await?;
+
+    Ok(stream::unfold(Some((pages, false)), |state| async move {
+        let (mut pages, after_full_page) = state?;
+
+        let page = match after_full_page {
+            true => timeout(NEXT_PAGE_TIMEOUT, pages.next()).await.ok()??,
+            false => pages.next().await?,
+        };
+
+        // a page that's not full must be the last one
+        let last_page = matches!(&page, Ok(page) if page.twins.len() < PAGE_SIZE as usize);
+
+        match last_page {
+            true => Some((page, None)),
+            false => Some((page, Some((pages, true)))),
+        }
+    }))
+}
+
+/// Searches the local host for the twins created from the given model.
+///
+/// Yields the DID of every twin found. A page that failed is yielded as an error and the search goes on.
+pub async fn search_twins_by_model(
+    host_api: &dyn HostApi,
+    model_did: &str,
+) -> Result<impl Stream<Item = Result<String, anyhow::Error>>, anyhow::Error> {
+    let filter = Filter {
+        properties: vec![Property {
+            key: MODEL_PROPERTY.to_string(),
+            value: Some(Value::UriValue(Uri {
+                value: model_did.to_string(),
+            })),
+        }],
+        location: None,
+        text: None,
+    };
+
+    let pages = search_local_pages(host_api, filter).await?;
+
+    Ok(pages.flat_map(|page| {
+        let twins_dids = match page {
+            Ok(page) => page
+                .twins
+                .into_iter()
+                .map(|twin| Ok(twin.id.value))
+                .collect::<Vec<Result<String, anyhow::Error>>>(),
+            Err(e) => vec![Err(e)],
+        };
+
+        stream::iter(twins_dids)
+    }))
+}
+
+/// Returns the DIDs of all the twins created from the given model, failing on the first error.
+pub async fn find_twins_by_model(
+    host_api: Arc<dyn HostApi>,
+    model_did: &str,
+) -> Result<Vec<String>, anyhow::Error> {
+    search_twins_by_model(host_api.as_ref(), model_did)
+        .await?
+        .collect::<Vec<Result<String, anyhow::Error>>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Returns the DIDs of all the twins of the host.
+pub async fn list_twins(host_api: Arc<dyn HostApi>) -> Result<Vec<String>, anyhow::Error> {
+    let twins = host_api.list_twins().await?;
+
+    Ok(twins.into_iter().map(|twin| twin.id.value).collect())
+}
@@ -0,0 +1,8 @@
+/// The definition of a single value, as declared in the feed or input metadata.
+#[derive(Debug, Clone)]
+pub struct ValueDefinition {
+    pub label: String,
+    pub data_type: String,
+    pub unit: String,
+    pub comment: String,
+}
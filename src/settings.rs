@@ -0,0 +1,462 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use iotics_grpc_client::auth_builder::IntoAuthBuilder;
+use iotics_identity::{create_agent_auth_token, create_twin_did_with_control_delegation, Config};
+use serde::Deserialize;
+use serde_aux::field_attributes::deserialize_number_from_string;
+use yansi::Paint;
+
+#[derive(Deserialize, Clone)]
+pub struct Settings {
+    pub iotics: IoticsSettings,
+    /// Network definitions, in addition to the `prod` and `dev` presets
+    #[serde(default)]
+    pub networks: HashMap<String, NetworkSettings>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct IoticsSettings {
+    pub host_address: String,
+    pub resolver_address: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub token_duration: usize,
+    pub user_did: String,
+    pub agent_did: String,
+    pub agent_key_name: String,
+    pub agent_name: String,
+    pub agent_secret: String,
+    /// The name of the network the host belongs to. Inferred from `host_address` if missing
+    pub network: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct NetworkSettings {
+    /// Hosts are discovered with a TXT record on `<host did>.<dns_suffix>`
+    pub dns_suffix: String,
+    #[serde(default = "default_scheme")]
+    pub grpc_scheme: String,
+    #[serde(default = "default_grpc_port")]
+    pub grpc_port: u16,
+    #[serde(default = "default_scheme")]
+    pub index_scheme: String,
+    pub index_port: Option<u16>,
+    /// The nameserver used for host discovery. Defaults to the system configuration
+    pub nameserver: Option<SocketAddr>,
+}
+
+fn default_grpc_port() -> u16 {
+    10001
+}
+
+fn default_scheme() -> String {
+    "https".to_string()
+}
+
+impl NetworkSettings {
+    pub fn preset(name: &str) -> Option<Self> {
+        let dns_suffix = match name {
+            "prod" => "iotics.space",
+            "dev" => "dev.iotics.space",
+            _ => return None,
+        };
+
+        Some(Self {
+            dns_suffix: dns_suffix.to_string(),
+            grpc_scheme: default_scheme(),
+            grpc_port: default_grpc_port(),
+            index_scheme: default_scheme(),
+            index_port: None,
+            nameserver: None,
+        })
+    }
+
+    pub fn dns_name(&self, host_did: &str) -> String {
+        format!("{}.{}", host_did, self.dns_suffix)
+    }
+
+    pub fn grpc_url(&self, host: &str) -> String {
+        format!("{}://{}:{}", self.grpc_scheme, host, self.grpc_port)
+    }
+
+    pub fn index_url(&self, host: &str) -> String {
+        match self.index_port {
+            Some(port) => format!("{}://{}:{}/index.json", self.index_scheme, host, port),
+            None => format!("{}://{}/index.json", self.index_scheme, host),
+        }
+    }
+
+    /// Extracts the host name from a gRPC URL such as `host_address`.
+    pub fn host_from_grpc_url(&self, url: &str) -> String {
+        let host = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+
+        host.strip_suffix(&format!(":{}", self.grpc_port))
+            .unwrap_or(host)
+            .to_string()
+    }
+
+    /// Strips the network DNS suffix from a host name, to keep the output short.
+    pub fn short_host(&self, host: &str) -> String {
+        host.strip_suffix(&format!(".{}", self.dns_suffix))
+            .unwrap_or(host)
+            .to_string()
+    }
+}
+
+impl Settings {
+    /// Loads the named configuration, printing it to stderr unless `quiet`,
+    /// so that it never mixes with the output of the command.
+    pub fn new(config_name: &str, quiet: bool) -> Result<Settings, anyhow::Error> {
+        let base_path = std::env::current_dir().expect("failed to determine the current directory");
+        let configuration_directory = base_path.join("configuration");
+
+        if quiet {
+            return Settings::load(&configuration_directory, config_name);
+        }
+
+        let stderr = io::stderr();
+        let stderr = &mut stderr.lock();
+
+        writeln!(stderr, "Loading base configuration...")?;
+        writeln!(
+            stderr,
+            "Loading configuration {:#?}...",
+            Paint::blue(&configuration_directory.join(config_name)),
+        )?;
+        stderr.flush()?;
+
+        let settings = Settings::load(&configuration_directory, config_name)?;
+
+        writeln!(
+            stderr,
+            "host {}",
+            Paint::blue(&settings.iotics.host_address),
+        )?;
+        writeln!(
+            stderr,
+            "resolver {}",
+            Paint::blue(&settings.iotics.resolver_address),
+        )?;
+        writeln!(
+            stderr,
+            "user did {}",
+            Paint::blue(&settings.iotics.user_did),
+        )?;
+        writeln!(
+            stderr,
+            "agent did {}",
+            Paint::blue(&settings.iotics.agent_did),
+        )?;
+        stderr.flush()?;
+
+        Ok(settings)
+    }
+
+    /// Loads the `base` configuration of the directory, layered with the named one, without writing anything.
+    pub fn load(
+        configuration_directory: &Path,
+        config_name: &str,
+    ) -> Result<Settings, anyhow::Error> {
+        let mut builder = config::Config::builder();
+
+        // Read the "base" configuration file
+        let config_path = configuration_directory.join("base");
+        builder = builder.add_source(config::File::new(
+            config_path.to_str().expect("this should never happen"),
+            config::FileFormat::Yaml,
+        ));
+
+        // Layer on the config-specific values.
+        let config_path = configuration_directory.join(config_name);
+        builder = builder.add_source(config::File::new(
+            config_path.to_str().expect("this should never happen"),
+            config::FileFormat::Yaml,
+        ));
+
+        Ok(builder.build()?.try_deserialize::<Settings>()?)
+    }
+
+    /// Finds the network by name, or the one the host belongs to if no name is given.
+    pub fn network(&self, name: Option<&str>) -> Result<NetworkSettings, anyhow::Error> {
+        let name = match name.or(self.iotics.network.as_deref()) {
+            Some(name) => name,
+            None if self.iotics.host_address.contains(".dev.") => "dev",
+            None => "prod",
+        };
+
+        self.networks
+            .get(name)
+            .cloned()
+            .or_else(|| NetworkSettings::preset(name))
+            .ok_or_else(|| anyhow::anyhow!("unknown network {:?}", name))
+    }
+}
+
+/// A token is renewed when it's this close to its expiry.
+const TOKEN_RENEWAL_MARGIN: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+struct CachedToken {
+    value: String,
+    expires_at: Instant,
+}
+
+/// The auth context of a single host.
+///
+/// The host address is fixed for the lifetime of the builder. Use `for_host` to talk to another host:
+/// the derived builder shares the settings and the token cache, never the host address.
+pub struct AuthBuilder {
+    settings: Arc<Settings>,
+    host_address: String,
+    token: Arc<Mutex<Option<CachedToken>>>,
+}
+
+impl AuthBuilder {
+    pub fn new(settings: Settings) -> Arc<Self> {
+        let host_address = settings.iotics.host_address.clone();
+
+        Arc::new(Self {
+            settings: Arc::new(settings),
+            host_address,
+            token: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Derives an independent auth context for another host, reusing the cached token while it's valid.
+    pub fn for_host(&self, host_address: String) -> Arc<Self> {
+        Arc::new(Self {
+            settings: self.settings.clone(),
+            host_address,
+            token: self.token.clone(),
+        })
+    }
+
+    /// Creates the DID of a twin controlled by the agent. The same key name always gives the same DID.
+    pub fn create_twin_did(&self, twin_key_name: &str) -> Result<String, anyhow::Error> {
+        let twin_name = format!("#{}", twin_key_name);
+        create_twin_did_with_control_delegation(&self.identity_config(), twin_key_name, &twin_name)
+    }
+
+    /// Whether the agent controls the twin DID, according to its document on the resolver.
+    ///
+    /// A DID unknown to the resolver isn't controlled.
+    pub async fn controls_twin_did(&self, twin_did: &str) -> Result<bool, anyhow::Error> {
+        let iotics = &self.settings.iotics;
+        let url = format!(
+            "{}/1.0/discover/{}",
+            iotics.resolver_address.trim_end_matches('/'),
+            twin_did
+        );
+
+        let response = reqwest::get(&url).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        let discovery = response.error_for_status()?.json::<Discovery>().await?;
+        let document = DidDocument::from_token(&discovery.token)
+            .map_err(|e| anyhow::anyhow!("failed to read the document of {}: {}", twin_did, e))?;
+
+        Ok(document.is_controlled_by(&iotics.agent_did))
+    }
+
+    fn identity_config(&self) -> Config {
+        let iotics = &self.settings.iotics;
+
+        Config {
+            resolver_address: iotics.resolver_address.clone(),
+            token_duration: iotics.token_duration as i64,
+            user_did: iotics.user_did.clone(),
+            agent_did: iotics.agent_did.clone(),
+            agent_key_name: iotics.agent_key_name.clone(),
+            agent_name: iotics.agent_name.clone(),
+            agent_secret: iotics.agent_secret.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct DiscoveryClaims {
+    doc: DidDocument,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidDocument {
+    #[serde(default)]
+    controller: Option<String>,
+    #[serde(default)]
+    delegate_control: Vec<DidDelegation>,
+}
+
+#[derive(Deserialize)]
+struct DidDelegation {
+    /// The key of the delegate, e.g. `did:iotics:...#agent`
+    controller: String,
+    #[serde(default)]
+    revoked: bool,
+}
+
+impl DidDocument {
+    /// Reads the document from the payload of the JWT returned by the resolver, whose signature isn't checked.
+    fn from_token(token: &str) -> Result<Self, anyhow::Error> {
+        let payload = token
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| anyhow::anyhow!("the token has no payload"))?;
+        let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)?;
+
+        Ok(serde_json::from_slice::<DiscoveryClaims>(&payload)?.doc)
+    }
+
+    /// Whether the agent is the controller, or has been delegated the control, of the DID.
+    fn is_controlled_by(&self, agent_did: &str) -> bool {
+        let is_agent = |controller: &str| controller.split('#').next() == Some(agent_did);
+
+        matches!(self.controller.as_deref(), Some(controller) if is_agent(controller))
+            || self
+                .delegate_control
+                .iter()
+                .any(|delegation| !delegation.revoked && is_agent(&delegation.controller))
+    }
+}
+
+impl IntoAuthBuilder for AuthBuilder {
+    fn get_host(&self) -> Result<String, anyhow::Error> {
+        Ok(self.host_address.clone())
+    }
+
+    fn get_token(&self) -> Result<String, anyhow::Error> {
+        let mut token_lock = self
+            .token
+            .lock()
+            .map_err(|_| anyhow::anyhow!("failed to lock the token mutex"))?;
+
+        if let Some(token) = token_lock.as_ref() {
+            if Instant::now() + TOKEN_RENEWAL_MARGIN < token.expires_at {
+                return Ok(token.value.clone());
+            }
+        }
+
+        let token_duration = self.settings.iotics.token_duration;
+
+        let created_at = Instant::now();
+        let token = create_agent_auth_token(&self.identity_config())?;
+        let token = format!("bearer {}", token);
+
+        token_lock.replace(CachedToken {
+            value: token.clone(),
+            expires_at: created_at + Duration::from_secs(token_duration as u64),
+        });
+
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(host_address: &str) -> Settings {
+        Settings {
+            iotics: IoticsSettings {
+                host_address: host_address.to_string(),
+                resolver_address: "https://resolver.example.com".to_string(),
+                token_duration: 60,
+                user_did: "did:iotics:user".to_string(),
+                agent_did: "did:iotics:agent".to_string(),
+                agent_key_name: "key".to_string(),
+                agent_name: "#agent".to_string(),
+                agent_secret: "secret".to_string(),
+                network: None,
+            },
+            networks: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn for_host_does_not_change_the_other_hosts() {
+        let auth_builder = AuthBuilder::new(settings("https://a.example.com:10001"));
+        let auth_builder_b = auth_builder.for_host("https://b.example.com:10001".to_string());
+        let auth_builder_c = auth_builder_b.for_host("https://c.example.com:10001".to_string());
+
+        assert_eq!(
+            auth_builder.get_host().unwrap(),
+            "https://a.example.com:10001"
+        );
+        assert_eq!(
+            auth_builder_b.get_host().unwrap(),
+            "https://b.example.com:10001"
+        );
+        assert_eq!(
+            auth_builder_c.get_host().unwrap(),
+            "https://c.example.com:10001"
+        );
+    }
+
+    #[test]
+    fn for_host_reuses_the_valid_token() {
+        let auth_builder = AuthBuilder::new(settings("https://a.example.com:10001"));
+        auth_builder.token.lock().unwrap().replace(CachedToken {
+            value: "bearer cached".to_string(),
+            expires_at: Instant::now() + Duration::from_secs(60),
+        });
+
+        let auth_builder_b = auth_builder.for_host("https://b.example.com:10001".to_string());
+
+        assert_eq!(auth_builder_b.get_token().unwrap(), "bearer cached");
+        assert_eq!(
+            auth_builder.get_host().unwrap(),
+            "https://a.example.com:10001"
+        );
+    }
+
+    #[test]
+    fn a_did_is_controlled_through_a_valid_delegation() {
+        let token = |doc: serde_json::Value| {
+            let payload = serde_json::json!({ "doc": doc }).to_string();
+            format!(
+                "header.{}.signature",
+                base64::encode_config(payload, base64::URL_SAFE_NO_PAD)
+            )
+        };
+        let delegated = |revoked: bool| {
+            serde_json::json!({
+                "id": "did:iotics:twin",
+                "controller": "",
+                "delegateControl": [
+                    { "id": "#deleg-0", "controller": "did:iotics:agent#agent", "revoked": revoked },
+                ],
+            })
+        };
+
+        let is_controlled = |doc| {
+            DidDocument::from_token(&token(doc))
+                .unwrap()
+                .is_controlled_by("did:iotics:agent")
+        };
+
+        assert!(is_controlled(delegated(false)));
+        assert!(!is_controlled(delegated(true)));
+        assert!(is_controlled(
+            serde_json::json!({ "controller": "did:iotics:agent" })
+        ));
+        assert!(!is_controlled(
+            serde_json::json!({ "controller": "did:iotics:other" })
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+const TYPE_TXT: u16 = 16;
+const CLASS_IN: u16 = 1;
+const RCODE_FORMAT_ERROR: u8 = 1;
+const RCODE_NAME_ERROR: u8 = 3;
+/// A TXT character string is at most 255 bytes long.
+const MAX_CHARACTER_STRING: usize = 255;
+
+/// A local nameserver answering the TXT queries over UDP from fixed records, like the DNS of a network.
+///
+/// The names without a record are answered as non-existent. The server stops when dropped.
+pub struct DnsServer {
+    address: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl DnsServer {
+    /// Serves the TXT records of every name, e.g. the host URL published for a host DID.
+    pub async fn start(records: HashMap<String, Vec<String>>) -> Result<Self, anyhow::Error> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await?;
+        let address = socket.local_addr()?;
+
+        let records = records
+            .into_iter()
+            .map(|(name, texts)| (normalize_name(&name), texts))
+            .collect::<HashMap<String, Vec<String>>>();
+
+        let task = tokio::spawn(async move {
+            let mut buffer = [0; 512];
+
+            while let Ok((read, peer)) = socket.recv_from(&mut buffer).await {
+                if let Some(response) = answer(&buffer[..read], &records) {
+                    let _ = socket.send_to(&response, peer).await;
+                }
+            }
+        });
+
+        Ok(Self { address, task })
+    }
+
+    /// The address to use as the nameserver, e.g. with `--nameserver`.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for DnsServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// The question of a query: the name, and where the question ends in the query.
+fn parse_question(query: &[u8]) -> Option<(String, u16, usize)> {
+    let mut labels = Vec::new();
+    let mut position = 12;
+
+    loop {
+        let length = *query.get(position)? as usize;
+        position += 1;
+
+        if length == 0 {
+            break;
+        }
+
+        let label = query.get(position..position + length)?;
+        labels.push(String::from_utf8_lossy(label).to_string());
+        position += length;
+    }
+
+    let query_type = u16::from_be_bytes([*query.get(position)?, *query.get(position + 1)?]);
+    // the type and the class
+    position += 4;
+
+    if position > query.len() {
+        return None;
+    }
+
+    Some((normalize_name(&labels.join(".")), query_type, position))
+}
+
+/// Builds the response to a standard query, `None` if it can't even be read.
+fn answer(query: &[u8], records: &HashMap<String, Vec<String>>) -> Option<Vec<u8>> {
+    if query.len() < 12 {
+        return None;
+    }
+
+    let (id, recursion_desired) = ([query[0], query[1]], query[2] & 0x01);
+
+    let (rcode, question_end, answers) = match parse_question(query) {
+        Some((name, query_type, question_end)) => match records.get(&name) {
+            Some(texts) if query_type == TYPE_TXT => (0, question_end, texts.as_slice()),
+            // the name exists, without any record of that type
+            Some(_) => (0, question_end, &[][..]),
+            None => (RCODE_NAME_ERROR, question_end, &[][..]),
+        },
+        None => (RCODE_FORMAT_ERROR, 12, &[][..]),
+    };
+
+    let mut response = Vec::with_capacity(512);
+    response.extend_from_slice(&id);
+    // a response, authoritative, echoing the recursion desired flag
+    response.push(0x84 | recursion_desired);
+    response.push(0x80 | rcode);
+    // one question if it could be read, the answers, no authority and no additional records
+    response.extend_from_slice(&(if question_end > 12 { 1u16 } else { 0 }).to_be_bytes());
+    response.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+    response.extend_from_slice(&[0, 0, 0, 0]);
+    response.extend_from_slice(&query[12..question_end]);
+
+    for text in answers {
+        let mut data = Vec::new();
+
+        for chunk in text.as_bytes().chunks(MAX_CHARACTER_STRING) {
+            data.push(chunk.len() as u8);
+            data.extend_from_slice(chunk);
+        }
+
+        // a record holds at least one, maybe empty, character string
+        if data.is_empty() {
+            data.push(0);
+        }
+
+        // the name is a pointer to the one of the question
+        response.extend_from_slice(&[0xc0, 0x0c]);
+        response.extend_from_slice(&TYPE_TXT.to_be_bytes());
+        response.extend_from_slice(&CLASS_IN.to_be_bytes());
+        response.extend_from_slice(&60u32.to_be_bytes());
+        response.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        response.extend_from_slice(&data);
+    }
+
+    Some(response)
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Set this variable to write the actual outputs to the golden files instead of comparing them.
+const UPDATE_VARIABLE: &str = "UPDATE_GOLDEN";
+
+const LOCALHOST: &str = "127.0.0.1:";
+
+/// Replaces the ports of the local servers, which change on every run, with `PORT`.
+///
+/// The ephemeral ports all have 5 digits, so the columns of a table stay aligned the same way.
+pub fn normalize_ports(output: &str) -> String {
+    let mut normalized = String::with_capacity(output.len());
+    let mut rest = output;
+
+    while let Some(index) = rest.find(LOCALHOST) {
+        let (before, after) = rest.split_at(index + LOCALHOST.len());
+        normalized.push_str(before);
+
+        let port_length = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+        if port_length > 0 {
+            normalized.push_str("PORT");
+        }
+
+        rest = &after[port_length..];
+    }
+
+    normalized.push_str(rest);
+    normalized
+}
+
+/// Compares the output of a command, with its ports normalized, to `tests/golden/<name>.txt`.
+///
+/// Run the tests with `UPDATE_GOLDEN=1` to accept a new output, then review the diff of the golden file.
+pub fn assert_golden(name: &str, output: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.txt", name));
+    let output = normalize_ports(output);
+
+    if std::env::var_os(UPDATE_VARIABLE).is_some() {
+        fs::write(&path, &output)
+            .unwrap_or_else(|e| panic!("failed to write {}: {}", path.display(), e));
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "failed to read {}: {}, run with {}=1 to create it",
+                path.display(),
+                e,
+                UPDATE_VARIABLE
+            )
+        })
+        // the golden files may be checked out with CRLF line endings
+        .replace("\r\n", "\n");
+
+    if output != expected {
+        let line = expected
+            .lines()
+            .zip(output.lines())
+            .position(|(expected_line, line)| expected_line != line)
+            .unwrap_or_else(|| expected.lines().count().min(output.lines().count()));
+
+        panic!(
+            "the output differs from {} at line {}, run with {}=1 to accept it\n\nexpected:\n{}\nactual:\n{}",
+            path.display(),
+            line + 1,
+            UPDATE_VARIABLE,
+            expected,
+            output
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A local HTTP server answering every request like the `index.json` of a host.
+///
+/// The server stops when dropped.
+pub struct IndexServer {
+    address: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl IndexServer {
+    /// Serves `{"version": <version>}`.
+    pub async fn start(version: &str) -> Result<Self, anyhow::Error> {
+        let body = serde_json::json!({ "version": version }).to_string();
+        Self::serve("200 OK", body).await
+    }
+
+    /// Answers every request with the given status, e.g. `503 Service Unavailable`.
+    pub async fn start_failing(status: &str) -> Result<Self, anyhow::Error> {
+        Self::serve(status, "".to_string()).await
+    }
+
+    /// The `ip:port` of the server, to be used as the host URL in the TXT records.
+    pub fn host(&self) -> String {
+        self.address.to_string()
+    }
+
+    async fn serve(status: &str, body: String) -> Result<Self, anyhow::Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+
+        let task = tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let response = response.clone();
+
+                tokio::spawn(async move {
+                    // the request itself doesn't matter, it's only read up to the end of the headers
+                    let mut request = Vec::new();
+                    let mut buffer = [0; 1024];
+
+                    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match socket.read(&mut buffer).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => request.extend_from_slice(&buffer[..read]),
+                        }
+                    }
+
+                    let _ = socket.write_all(response.as_bytes()).await;
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        Ok(Self { address, task })
+    }
+}
+
+impl Drop for IndexServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,683 @@
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use iotics_grpc_client::common::{
+    FeedData, HostId, LangLiteral, Property, Scope, TwinId, Uri, Value, Visibility,
+};
+use iotics_grpc_client::search::Filter;
+use iotics_grpc_client::twin::PAGE_SIZE;
+
+use crate::operations::describe::TwinDescription;
+use crate::operations::host_api::{
+    FeedMeta, FoundTwin, HostApi, InputMeta, SearchPage, TwinMeta, TwinSummary,
+};
+use crate::operations::twins::{LABEL_PROPERTY, MODEL_PROPERTY};
+use crate::operations::value_definitions::ValueDefinition;
+
+#[derive(Debug, Clone)]
+pub struct MockFeed {
+    pub id: String,
+    pub store_last: bool,
+    pub properties: Vec<Property>,
+    pub values: Vec<ValueDefinition>,
+    /// What a follower receives, in order. Shared data is appended
+    pub data: Vec<FeedData>,
+}
+
+impl MockFeed {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            store_last: false,
+            properties: Vec::new(),
+            values: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    pub fn store_last(mut self) -> Self {
+        self.store_last = true;
+        self
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.properties.push(Property {
+            key: LABEL_PROPERTY.to_string(),
+            value: Some(Value::LangLiteralValue(LangLiteral {
+                value: label.to_string(),
+                lang: "en".to_string(),
+            })),
+        });
+        self
+    }
+
+    pub fn with_value(mut self, label: &str, data_type: &str, unit: &str, comment: &str) -> Self {
+        self.values.push(ValueDefinition {
+            label: label.to_string(),
+            data_type: data_type.to_string(),
+            unit: unit.to_string(),
+            comment: comment.to_string(),
+        });
+        self
+    }
+
+    /// Adds a JSON sample, with no timestamp so that the output stays the same between runs.
+    pub fn with_json(mut self, json: serde_json::Value) -> Self {
+        self.data.push(FeedData {
+            occurred_at: None,
+            mime: "application/json".to_string(),
+            data: json.to_string().into_bytes(),
+        });
+        self
+    }
+
+    /// Adds a JSON sample shared at the given UNIX time, in seconds.
+    pub fn with_json_at(mut self, json: serde_json::Value, seconds: i64) -> Self {
+        self.data.push(FeedData {
+            occurred_at: Some(prost_types::Timestamp { seconds, nanos: 0 }),
+            mime: "application/json".to_string(),
+            data: json.to_string().into_bytes(),
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockInput {
+    pub id: String,
+    pub properties: Vec<Property>,
+    pub values: Vec<ValueDefinition>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockTwin {
+    pub id: String,
+    pub visibility: Visibility,
+    pub location: Option<(f64, f64)>,
+    pub properties: Vec<Property>,
+    pub feeds: Vec<MockFeed>,
+    pub inputs: Vec<MockInput>,
+}
+
+impl MockTwin {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            visibility: Visibility::Private,
+            location: None,
+            properties: Vec::new(),
+            feeds: Vec::new(),
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn with_property(mut self, key: &str, value: Value) -> Self {
+        self.properties.push(Property {
+            key: key.to_string(),
+            value: Some(value),
+        });
+        self
+    }
+
+    pub fn with_uri(self, key: &str, uri: &str) -> Self {
+        self.with_property(
+            key,
+            Value::UriValue(Uri {
+                value: uri.to_string(),
+            }),
+        )
+    }
+
+    pub fn with_label(self, label: &str) -> Self {
+        self.with_property(
+            LABEL_PROPERTY,
+            Value::LangLiteralValue(LangLiteral {
+                value: label.to_string(),
+                lang: "en".to_string(),
+            }),
+        )
+    }
+
+    pub fn with_model(self, model_did: &str) -> Self {
+        self.with_uri(MODEL_PROPERTY, model_did)
+    }
+
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn with_location(mut self, lat: f64, lon: f64) -> Self {
+        self.location = Some((lat, lon));
+        self
+    }
+
+    pub fn with_feed(mut self, feed: MockFeed) -> Self {
+        self.feeds.push(feed);
+        self
+    }
+
+    pub fn with_input(mut self, id: &str, values: Vec<ValueDefinition>) -> Self {
+        self.inputs.push(MockInput {
+            id: id.to_string(),
+            properties: Vec::new(),
+            values,
+        });
+        self
+    }
+
+    fn feed_mut(&mut self, feed_id: &str) -> Result<&mut MockFeed, anyhow::Error> {
+        let twin_id = self.id.clone();
+
+        self.feeds
+            .iter_mut()
+            .find(|feed| feed.id == feed_id)
+            .ok_or_else(|| anyhow::anyhow!("feed {} of twin {} not found", feed_id, twin_id))
+    }
+
+    fn matches(&self, filter: &Filter) -> bool {
+        let properties_match = filter
+            .properties
+            .iter()
+            .all(|property| self.properties.contains(property));
+
+        let text_matches = match &filter.text {
+            Some(text) => self
+                .properties
+                .iter()
+                .any(|property| match &property.value {
+                    Some(Value::LangLiteralValue(lang_literal)) => {
+                        lang_literal.value.contains(text)
+                    }
+                    Some(Value::StringLiteralValue(string_literal)) => {
+                        string_literal.value.contains(text)
+                    }
+                    Some(Value::LiteralValue(literal)) => literal.value.contains(text),
+                    _ => false,
+                }),
+            None => true,
+        };
+
+        properties_match && text_matches
+    }
+}
+
+struct HostState {
+    host_did: String,
+    twins: Vec<MockTwin>,
+    failing_deletions: HashSet<String>,
+    offline: bool,
+    rejects_token: bool,
+    /// The search pages the callers have read
+    pages_read: Arc<AtomicUsize>,
+}
+
+impl HostState {
+    fn twin_mut(&mut self, twin_did: &str) -> Result<&mut MockTwin, anyhow::Error> {
+        self.twins
+            .iter_mut()
+            .find(|twin| twin.id == twin_did)
+            .ok_or_else(|| anyhow::anyhow!("twin {} not found", twin_did))
+    }
+
+    /// The twins matching the filter, page by page. A remote host, with a `host_id`, only shares its public twins.
+    ///
+    /// Like a real host, the last page is never full: an empty page follows a full one.
+    fn search_pages(&self, filter: &Filter, host_id: Option<HostId>) -> Vec<SearchPage> {
+        let twins = self
+            .twins
+            .iter()
+            .filter(|twin| host_id.is_none() || twin.visibility == Visibility::Public)
+            .filter(|twin| twin.matches(filter))
+            .map(|twin| FoundTwin {
+                id: TwinId {
+                    value: twin.id.clone(),
+                },
+                visibility: Some(twin.visibility),
+                properties: twin.properties.clone(),
+            })
+            .collect::<Vec<FoundTwin>>();
+
+        // a host without any match still answers, with an empty page
+        if twins.is_empty() {
+            return vec![SearchPage {
+                host_id,
+                twins: Vec::new(),
+            }];
+        }
+
+        twins
+            .chunks(PAGE_SIZE as usize)
+            .map(|twins| SearchPage {
+                host_id: host_id.clone(),
+                twins: twins.to_vec(),
+            })
+            .collect()
+    }
+}
+
+type Hosts = Arc<Mutex<BTreeMap<String, HostState>>>;
+
+/// The DIDs whose identity the agent controls, like a resolver would tell.
+type Identities = Arc<Mutex<HashSet<String>>>;
+
+/// Hosts sharing the same network: a global search goes through all of them.
+#[derive(Clone, Default)]
+pub struct MockNetwork {
+    hosts: Hosts,
+    identities: Identities,
+}
+
+impl MockNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an empty host, reachable at the given gRPC URL.
+    pub fn add_host(&self, host_address: &str, host_did: &str) -> Arc<MockHost> {
+        self.hosts.lock().expect("this should not happen").insert(
+            host_address.to_string(),
+            HostState {
+                host_did: host_did.to_string(),
+                twins: Vec::new(),
+                failing_deletions: HashSet::new(),
+                offline: false,
+                rejects_token: false,
+                pages_read: Arc::new(AtomicUsize::new(0)),
+            },
+        );
+
+        Arc::new(MockHost {
+            host_address: host_address.to_string(),
+            hosts: self.hosts.clone(),
+            identities: self.identities.clone(),
+        })
+    }
+}
+
+/// An in-memory host, implementing `HostApi` for the tests.
+///
+/// Every call fails if the host is offline or is not part of the network.
+/// The twins can only be upserted with an identity the agent controls.
+pub struct MockHost {
+    host_address: String,
+    hosts: Hosts,
+    identities: Identities,
+}
+
+impl MockHost {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, HostState>> {
+        self.hosts.lock().expect("this should not happen")
+    }
+
+    fn with_state<T, F>(&self, f: F) -> Result<T, anyhow::Error>
+    where
+        F: FnOnce(&mut HostState) -> Result<T, anyhow::Error>,
+    {
+        let mut hosts_lock = self.lock();
+        let state = hosts_lock
+            .get_mut(&self.host_address)
+            .ok_or_else(|| anyhow::anyhow!("no host at {}", self.host_address))?;
+
+        if state.offline {
+            anyhow::bail!("host {} is unreachable", self.host_address);
+        }
+
+        f(state)
+    }
+
+    /// Runs `f` on the twin, stored on this host or on the remote host with the given ID.
+    fn with_twin<T, F>(
+        &self,
+        host_id: Option<HostId>,
+        twin_did: &str,
+        f: F,
+    ) -> Result<T, anyhow::Error>
+    where
+        F: FnOnce(&mut MockTwin) -> Result<T, anyhow::Error>,
+    {
+        let host_did = match host_id {
+            Some(host_id) => host_id.value,
+            None => return self.with_state(|state| f(state.twin_mut(twin_did)?)),
+        };
+
+        let mut hosts_lock = self.lock();
+        let state = hosts_lock
+            .values_mut()
+            .find(|state| state.host_did == host_did && !state.offline)
+            .ok_or_else(|| anyhow::anyhow!("host {} is unreachable", host_did))?;
+
+        f(state.twin_mut(twin_did)?)
+    }
+
+    /// Adds a twin whose identity is controlled by another agent.
+    pub fn add_twin(&self, twin: MockTwin) {
+        if let Some(state) = self.lock().get_mut(&self.host_address) {
+            state.twins.push(twin);
+        }
+    }
+
+    /// Adds a twin whose identity is controlled by the agent.
+    pub fn add_own_twin(&self, twin: MockTwin) {
+        self.control_identity(&twin.id);
+        self.add_twin(twin);
+    }
+
+    /// Gives the agent the control of the DID, whether a twin has it or not.
+    pub fn control_identity(&self, twin_did: &str) {
+        self.identities
+            .lock()
+            .expect("this should not happen")
+            .insert(twin_did.to_string());
+    }
+
+    pub fn twin(&self, twin_did: &str) -> Option<MockTwin> {
+        self.lock()
+            .get(&self.host_address)?
+            .twins
+            .iter()
+            .find(|twin| twin.id == twin_did)
+            .cloned()
+    }
+
+    pub fn twins_dids(&self) -> Vec<String> {
+        self.lock()
+            .get(&self.host_address)
+            .map(|state| state.twins.iter().map(|twin| twin.id.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Makes the deletion of the twin fail, leaving it on the host.
+    pub fn fail_deletion(&self, twin_did: &str) {
+        if let Some(state) = self.lock().get_mut(&self.host_address) {
+            state.failing_deletions.insert(twin_did.to_string());
+        }
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        if let Some(state) = self.lock().get_mut(&self.host_address) {
+            state.offline = offline;
+        }
+    }
+
+    /// How many pages of the searches made on this host have been read, e.g. to check that a search is dropped early.
+    pub fn pages_read(&self) -> usize {
+        self.lock()
+            .get(&self.host_address)
+            .map(|state| state.pages_read.load(Ordering::SeqCst))
+            .unwrap_or_default()
+    }
+
+    /// Makes `authenticate` fail like a host that doesn't accept the token of the agent.
+    pub fn reject_token(&self) {
+        if let Some(state) = self.lock().get_mut(&self.host_address) {
+            state.rejects_token = true;
+        }
+    }
+}
+
+#[async_trait]
+impl HostApi for MockHost {
+    fn host_address(&self) -> String {
+        self.host_address.clone()
+    }
+
+    fn for_host(&self, host_address: String) -> Arc<dyn HostApi> {
+        Arc::new(MockHost {
+            host_address,
+            hosts: self.hosts.clone(),
+            identities: self.identities.clone(),
+        })
+    }
+
+    async fn authenticate(&self) -> Result<(), anyhow::Error> {
+        self.with_state(|state| match state.rejects_token {
+            true => Err(anyhow::anyhow!(
+                "the host rejected the token: unauthenticated"
+            )),
+            false => Ok(()),
+        })
+    }
+
+    async fn create_twin_identity(&self, twin_key_name: &str) -> Result<String, anyhow::Error> {
+        let twin_did = self.with_state(|_| Ok(format!("did:iotics:{}", twin_key_name)))?;
+        self.control_identity(&twin_did);
+
+        Ok(twin_did)
+    }
+
+    async fn controls_twin_identity(&self, twin_did: &str) -> Result<bool, anyhow::Error> {
+        Ok(self
+            .identities
+            .lock()
+            .expect("this should not happen")
+            .contains(twin_did))
+    }
+
+    async fn search(
+        &self,
+        filter: Filter,
+        scope: Scope,
+        timeout: Option<Duration>,
+    ) -> Result<BoxStream<'static, Result<SearchPage, anyhow::Error>>, anyhow::Error> {
+        let (mut pages, pages_read) = self.with_state(|state| {
+            Ok((state.search_pages(&filter, None), state.pages_read.clone()))
+        })?;
+
+        if scope == Scope::Global {
+            for (host_address, state) in self.lock().iter() {
+                if *host_address != self.host_address && !state.offline {
+                    let host_id = HostId {
+                        value: state.host_did.clone(),
+                    };
+                    pages.extend(state.search_pages(&filter, Some(host_id)));
+                }
+            }
+        }
+
+        let pages = stream::iter(pages.into_iter().map(Ok)).inspect(move |_| {
+            pages_read.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // a real search only ends on timeout
+        match timeout {
+            Some(_) => Ok(pages.boxed()),
+            None => Ok(pages.chain(stream::pending()).boxed()),
+        }
+    }
+
+    async fn list_twins(&self) -> Result<Vec<TwinSummary>, anyhow::Error> {
+        self.with_state(|state| {
+            Ok(state
+                .twins
+                .iter()
+                .map(|twin| TwinSummary {
+                    id: TwinId {
+                        value: twin.id.clone(),
+                    },
+                    visibility: Some(twin.visibility),
+                })
+                .collect())
+        })
+    }
+
+    async fn delete_twin(&self, twin_did: &str) -> Result<(), anyhow::Error> {
+        self.with_state(|state| {
+            if state.failing_deletions.contains(twin_did) {
+                anyhow::bail!("failed to delete twin {}", twin_did);
+            }
+
+            state.twin_mut(twin_did)?;
+            state.twins.retain(|twin| twin.id != twin_did);
+
+            Ok(())
+        })
+    }
+
+    async fn upsert_twin(&self, description: &TwinDescription) -> Result<(), anyhow::Error> {
+        if !self.controls_twin_identity(&description.twin_did).await? {
+            anyhow::bail!(
+                "the agent doesn't control the identity of twin {}",
+                description.twin_did
+            );
+        }
+
+        self.with_state(|state| {
+            let previous = state
+                .twins
+                .iter()
+                .position(|twin| twin.id == description.twin_did)
+                .map(|index| state.twins.remove(index));
+
+            // the data shared on the feeds that are kept isn't lost
+            let feeds = description
+                .feeds
+                .iter()
+                .map(|feed| MockFeed {
+                    id: feed.id.clone(),
+                    store_last: feed.store_last.unwrap_or_default(),
+                    properties: feed.properties.clone(),
+                    values: feed.values.clone(),
+                    data: previous
+                        .iter()
+                        .flat_map(|twin| &twin.feeds)
+                        .find(|previous_feed| previous_feed.id == feed.id)
+                        .map(|previous_feed| previous_feed.data.clone())
+                        .unwrap_or_default(),
+                })
+                .collect();
+
+            state.twins.push(MockTwin {
+                id: description.twin_did.clone(),
+                visibility: match description.is_public() {
+                    true => Visibility::Public,
+                    false => Visibility::Private,
+                },
+                location: description.location,
+                properties: description.properties.clone(),
+                feeds,
+                inputs: description
+                    .inputs
+                    .iter()
+                    .map(|input| MockInput {
+                        id: input.id.clone(),
+                        properties: input.properties.clone(),
+                        values: input.values.clone(),
+                    })
+                    .collect(),
+            });
+
+            Ok(())
+        })
+    }
+
+    async fn describe_twin(
+        &self,
+        twin_id: TwinId,
+        host_id: Option<HostId>,
+    ) -> Result<TwinMeta, anyhow::Error> {
+        self.with_twin(host_id, &twin_id.value, |twin| {
+            Ok(TwinMeta {
+                visibility: Some(twin.visibility),
+                location: twin.location,
+                properties: twin.properties.clone(),
+                feeds: twin
+                    .feeds
+                    .iter()
+                    .map(|feed| FeedMeta {
+                        id: feed.id.clone(),
+                        store_last: feed.store_last,
+                        properties: feed.properties.clone(),
+                    })
+                    .collect(),
+                inputs: twin
+                    .inputs
+                    .iter()
+                    .map(|input| InputMeta {
+                        id: input.id.clone(),
+                        properties: input.properties.clone(),
+                    })
+                    .collect(),
+                raw: format!("{:#?}", twin),
+            })
+        })
+    }
+
+    async fn describe_feed(
+        &self,
+        twin_id: TwinId,
+        feed_id: &str,
+        host_id: Option<HostId>,
+    ) -> Result<Vec<ValueDefinition>, anyhow::Error> {
+        self.with_twin(host_id, &twin_id.value, |twin| {
+            Ok(twin.feed_mut(feed_id)?.values.clone())
+        })
+    }
+
+    async fn describe_input(
+        &self,
+        twin_id: TwinId,
+        input_id: &str,
+        host_id: Option<HostId>,
+    ) -> Result<Vec<ValueDefinition>, anyhow::Error> {
+        self.with_twin(host_id, &twin_id.value, |twin| {
+            twin.inputs
+                .iter()
+                .find(|input| input.id == input_id)
+                .map(|input| input.values.clone())
+                .ok_or_else(|| anyhow::anyhow!("input {} of twin {} not found", input_id, twin.id))
+        })
+    }
+
+    /// Yields the data of the feed, then ends as if the stream broke.
+    async fn follow(
+        &self,
+        host_id: Option<HostId>,
+        twin_id: TwinId,
+        feed_id: String,
+        follower_twin_id: TwinId,
+    ) -> Result<BoxStream<'static, Result<FeedData, anyhow::Error>>, anyhow::Error> {
+        self.with_state(|state| state.twin_mut(&follower_twin_id.value).map(|_| ()))?;
+
+        let data = self.with_twin(host_id, &twin_id.value, |twin| {
+            Ok(twin.feed_mut(&feed_id)?.data.clone())
+        })?;
+
+        Ok(stream::iter(data.into_iter().map(Ok)).boxed())
+    }
+
+    async fn fetch_last_stored(
+        &self,
+        host_id: Option<HostId>,
+        twin_id: TwinId,
+        feed_id: String,
+        follower_twin_id: TwinId,
+    ) -> Result<Option<FeedData>, anyhow::Error> {
+        self.with_state(|state| state.twin_mut(&follower_twin_id.value).map(|_| ()))?;
+
+        self.with_twin(host_id, &twin_id.value, |twin| {
+            let feed = twin.feed_mut(&feed_id)?;
+
+            match feed.store_last {
+                true => Ok(feed.data.last().cloned()),
+                false => Ok(None),
+            }
+        })
+    }
+
+    async fn share_data(
+        &self,
+        twin_id: TwinId,
+        feed_id: String,
+        feed_data: FeedData,
+    ) -> Result<(), anyhow::Error> {
+        self.with_twin(None, &twin_id.value, |twin| {
+            twin.feed_mut(&feed_id)?.data.push(feed_data);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,54 @@
+//! Test doubles to run the commands and the operations offline.
+//!
+//! `MockNetwork` holds in-memory hosts implementing `HostApi`, `IndexServer` serves the `index.json`
+//! of a host and `DnsServer` is the nameserver publishing the hosts URL, to pass as `--nameserver`.
+//! `assert_golden` compares the output of a command to a stored golden file.
+//!
+//! The hosts are mocked behind `HostApi`, `GrpcHostApi` itself isn't exercised.
+
+mod dns_server;
+mod golden;
+mod index_server;
+mod mock_host;
+
+pub use dns_server::DnsServer;
+pub use golden::{assert_golden, normalize_ports};
+pub use index_server::IndexServer;
+pub use mock_host::{MockFeed, MockHost, MockInput, MockNetwork, MockTwin};
+
+use std::collections::HashMap;
+
+use crate::settings::{IoticsSettings, NetworkSettings, Settings};
+
+/// The name of the network returned by `settings`.
+pub const NETWORK: &str = "test";
+
+/// A network whose hosts serve their `index.json` over plain HTTP, like `IndexServer`.
+pub fn network() -> NetworkSettings {
+    NetworkSettings {
+        dns_suffix: "iotics.test".to_string(),
+        grpc_scheme: "https".to_string(),
+        grpc_port: 10001,
+        index_scheme: "http".to_string(),
+        index_port: None,
+        nameserver: None,
+    }
+}
+
+/// Settings for the given host, on the `test` network. The identity is never used by the mocks.
+pub fn settings(host_address: &str) -> Settings {
+    Settings {
+        iotics: IoticsSettings {
+            host_address: host_address.to_string(),
+            resolver_address: "https://resolver.iotics.test".to_string(),
+            token_duration: 60,
+            user_did: "did:iotics:user".to_string(),
+            agent_did: "did:iotics:agent".to_string(),
+            agent_key_name: "key".to_string(),
+            agent_name: "#agent".to_string(),
+            agent_secret: "secret".to_string(),
+            network: Some(NETWORK.to_string()),
+        },
+        networks: HashMap::from([(NETWORK.to_string(), network())]),
+    }
+}
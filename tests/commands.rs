@@ -0,0 +1,659 @@
+use std::collections::HashMap;
+use structopt::StructOpt;
+use yansi::Paint;
+
+use iotics_grpc_client::common::Visibility;
+use iotics_grpc_client::twin::PAGE_SIZE;
+
+use iotics_cli::commands::check::{Check, CheckArgs};
+use iotics_cli::commands::clone_model::{CloneModel, CloneModelArgs};
+use iotics_cli::commands::delete_all_twins::{DeleteAllTwins, DeleteAllTwinsArgs};
+use iotics_cli::commands::delete_twins_by_model::{DeleteTwinsByModel, DeleteTwinsByModelArgs};
+use iotics_cli::commands::describe_twin::{DescribeTwin, DescribeTwinArgs};
+use iotics_cli::commands::describe_twins::{DescribeTwins, DescribeTwinsArgs};
+use iotics_cli::commands::export::{Export, ExportArgs};
+use iotics_cli::commands::gc::{Gc, GcArgs};
+use iotics_cli::commands::host::diff::{HostDiff, HostDiffArgs};
+use iotics_cli::commands::import::{Import, ImportArgs};
+use iotics_cli::commands::list_hosts::twins_count::count_twins;
+use iotics_cli::commands::list_hosts::{ListHosts, ListHostsArgs};
+use iotics_cli::commands::model::describe::{ModelDescribe, ModelDescribeArgs};
+use iotics_cli::commands::model::instantiate::{ModelInstantiate, ModelInstantiateArgs};
+use iotics_cli::commands::model::list::{ModelList, ModelListArgs};
+use iotics_cli::commands::replay::ReplayArgs;
+use iotics_cli::commands::RunnableCommand;
+use iotics_cli::operations::gc::{CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY};
+use iotics_cli::operations::host_api::HostApi;
+use iotics_cli::operations::portable::{PortableTwin, PortableValue};
+use iotics_cli::operations::twins::find_twins_by_model;
+use iotics_cli::testing::{self, DnsServer, IndexServer, MockFeed, MockNetwork, MockTwin};
+
+const MODEL_DID: &str = "did:iotics:model";
+const LOCAL_HOST: &str = "https://local.iotics.test:10001";
+const HOST_TWIN_TYPE: &str = "http://data.iotics.com/public#HostTwin";
+const TYPE_PROPERTY: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+#[tokio::test]
+async fn list_hosts_reports_every_host() {
+    Paint::disable();
+
+    let network = testing::network();
+    let local_index = IndexServer::start("4.0.1").await.unwrap();
+    let remote_index = IndexServer::start("3.2.0").await.unwrap();
+
+    let mock_network = MockNetwork::new();
+    let local = mock_network.add_host(&network.grpc_url(&local_index.host()), "did:iotics:local");
+    let remote =
+        mock_network.add_host(&network.grpc_url(&remote_index.host()), "did:iotics:remote");
+    // no TXT record is published for this one
+    let hidden = mock_network.add_host("https://hidden.iotics.test:10001", "did:iotics:hidden");
+
+    for (host, host_did) in [
+        (&local, "did:iotics:local"),
+        (&remote, "did:iotics:remote"),
+        (&hidden, "did:iotics:hidden"),
+    ] {
+        host.add_twin(
+            MockTwin::new(host_did)
+                .with_uri(TYPE_PROPERTY, HOST_TWIN_TYPE)
+                .with_visibility(Visibility::Public),
+        );
+    }
+    local.add_twin(MockTwin::new("did:iotics:twin-0"));
+    local.add_twin(MockTwin::new("did:iotics:twin-1"));
+
+    let dns_server = DnsServer::start(HashMap::from([(
+        network.dns_name("did:iotics:remote"),
+        vec![remote_index.host()],
+    )]))
+    .await
+    .unwrap();
+    let nameserver = dns_server.address().to_string();
+
+    let opts = ListHostsArgs::from_iter([
+        "list-hosts",
+        "--config",
+        "test",
+        "--with-version",
+        "--with-twins",
+        "--count-cache-ttl",
+        "0",
+        "--sort-by",
+        "twins",
+        "--nameserver",
+        &nameserver,
+    ]);
+
+    let mut stdout = Vec::new();
+    let settings = testing::settings(&local.host_address());
+    ListHosts::with_host_api(&mut stdout, opts, settings, local.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    let rows = output
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .collect::<Vec<Vec<&str>>>();
+
+    assert_eq!(rows[0], vec!["#", "Host", "DID", "Version", "Twins"]);
+    let (local_host, remote_host) = (local_index.host(), remote_index.host());
+    assert_eq!(rows[1], vec!["1", local_host.as_str(), "?", "4.0.1", "3"]);
+    assert_eq!(
+        rows[2],
+        vec!["2", remote_host.as_str(), "did:iotics:remote", "3.2.0", "1"]
+    );
+    assert_eq!(
+        output.lines().last(),
+        Some("Found 3 hosts, 2 reachable, 1 unreachable, 0 timed out, 4 twins in total.")
+    );
+}
+
+#[tokio::test]
+async fn host_diff_ignores_the_unknown_versions() {
+    Paint::disable();
+
+    let snapshot = |name: &str, versions: [serde_json::Value; 2]| {
+        let path = std::env::temp_dir().join(format!(
+            "iotics-cli-snapshot-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let json = serde_json::json!({
+            "taken_at": "2026-10-18T00:00:00Z",
+            "hosts": [
+                {"host_did": "did:iotics:a", "url": "a", "version": versions[0], "twins_count": null},
+                {"host_did": "did:iotics:b", "url": "b", "version": versions[1], "twins_count": null},
+            ],
+        });
+        std::fs::write(&path, json.to_string()).unwrap();
+        path
+    };
+
+    // `""` is how the older snapshots stored a version that could not be fetched
+    let old = snapshot("old", [serde_json::json!("4.0.1"), serde_json::json!("")]);
+    let new = snapshot("new", [serde_json::Value::Null, serde_json::json!("4.0.1")]);
+
+    let opts = HostDiffArgs::from_iter(["diff", old.to_str().unwrap(), new.to_str().unwrap()]);
+
+    let mut stdout = Vec::new();
+    HostDiff::new(&mut stdout, opts)
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+
+    std::fs::remove_file(&old).unwrap();
+    std::fs::remove_file(&new).unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("Upgraded (0)"));
+    assert!(output.contains("Downgraded (0)"));
+}
+
+#[tokio::test]
+async fn delete_twins_by_model_counts_the_deleted_twins() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(MockTwin::new(MODEL_DID));
+    for index in 0..3 {
+        host.add_twin(MockTwin::new(&format!("did:iotics:twin-{}", index)).with_model(MODEL_DID));
+    }
+    host.fail_deletion("did:iotics:twin-1");
+
+    let opts = DeleteTwinsByModelArgs::from_iter([
+        "delete-twins-by-model",
+        "--config",
+        "test",
+        "--model-did",
+        MODEL_DID,
+        "--delete-model",
+    ]);
+
+    let mut stdout = Vec::new();
+    DeleteTwinsByModel::with_host_api(&mut stdout, opts, host.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("Found 3 twins for model did:iotics:model. Deleting..."));
+    assert!(output.contains("Deleted 2 twins for model did:iotics:model."));
+    assert_eq!(host.twins_dids(), vec!["did:iotics:twin-1"]);
+}
+
+#[tokio::test]
+async fn delete_all_twins_reports_an_unreachable_host() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(MockTwin::new("did:iotics:twin-0"));
+    host.set_offline(true);
+
+    let opts = DeleteAllTwinsArgs::from_iter(["delete-all-twins", "--config", "test"]);
+
+    let mut stdout = Vec::new();
+    DeleteAllTwins::with_host_api(&mut stdout, opts, host.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("is unreachable"));
+    assert!(output.ends_with("Done.\n"));
+
+    host.set_offline(false);
+    assert_eq!(host.twins_dids(), vec!["did:iotics:twin-0"]);
+}
+
+#[tokio::test]
+async fn describe_twin_renders_the_feeds_values() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(
+        MockTwin::new("did:iotics:twin-0")
+            .with_label("Weather station")
+            .with_location(51.5, -0.12)
+            .with_feed(MockFeed::new("temperature").store_last().with_value(
+                "reading",
+                "decimal",
+                "http://qudt.org/vocab/unit/DEG_C",
+                "In celsius",
+            )),
+    );
+
+    let opts = DescribeTwinArgs::from_iter([
+        "describe-twin",
+        "--config",
+        "test",
+        "--twin-did",
+        "did:iotics:twin-0",
+    ]);
+
+    let mut stdout = Vec::new();
+    DescribeTwin::with_host_api(&mut stdout, opts, host)
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("Label       Weather station"));
+    assert!(output.contains("Location    51.5, -0.12"));
+    assert!(output.contains("Feeds (1)"));
+    assert!(output.contains("temperature"));
+    assert!(output.contains("store last"));
+    assert!(output.contains("In celsius"));
+}
+
+#[tokio::test]
+async fn export_writes_a_twin_per_line() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(MockTwin::new(MODEL_DID));
+    for index in 0..2 {
+        host.add_twin(
+            MockTwin::new(&format!("did:iotics:twin-{}", index))
+                .with_model(MODEL_DID)
+                .with_feed(MockFeed::new("temperature").store_last().with_value(
+                    "reading",
+                    "decimal",
+                    "http://qudt.org/vocab/unit/DEG_C",
+                    "In celsius",
+                )),
+        );
+    }
+
+    let output =
+        std::env::temp_dir().join(format!("iotics-cli-export-{}.ndjson", std::process::id()));
+    let opts = ExportArgs::from_iter([
+        "export",
+        "--config",
+        "test",
+        // also selected by model, exported once
+        "--twin-did",
+        "did:iotics:twin-0",
+        "--model-did",
+        MODEL_DID,
+        "--output",
+        output.to_str().unwrap(),
+    ]);
+
+    let mut stdout = Vec::new();
+    Export::with_host_api(&mut stdout, opts, host)
+        .run()
+        .await
+        .unwrap();
+
+    let archive = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+
+    let twins = archive
+        .lines()
+        .map(|line| serde_json::from_str::<PortableTwin>(line).unwrap())
+        .collect::<Vec<PortableTwin>>();
+
+    assert_eq!(twins.len(), 2);
+    assert_eq!(twins[0].twin_did, "did:iotics:twin-0");
+    assert_eq!(
+        twins[0].properties[0].value,
+        Some(PortableValue::Uri {
+            uri: MODEL_DID.to_string()
+        })
+    );
+    assert_eq!(twins[1].feeds[0].id, "temperature");
+    assert_eq!(twins[1].feeds[0].store_last, Some(true));
+    assert_eq!(
+        twins[1].feeds[0].values[0].unit,
+        "http://qudt.org/vocab/unit/DEG_C"
+    );
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("Exported 2 twins, 0 failed."));
+}
+
+#[tokio::test]
+async fn import_recreates_an_exported_directory_on_another_host() {
+    Paint::disable();
+
+    let mock_network = MockNetwork::new();
+    let source = mock_network.add_host(LOCAL_HOST, "did:iotics:local");
+    let target = mock_network.add_host("https://remote.iotics.test:10001", "did:iotics:remote");
+    let feed = MockFeed::new("temperature")
+        .store_last()
+        .with_label("Temperature");
+    source.add_twin(MockTwin::new(MODEL_DID).with_label("Weather station model"));
+    source.add_twin(
+        MockTwin::new("did:iotics:twin-0")
+            .with_model(MODEL_DID)
+            .with_location(51.5, -0.12)
+            .with_feed(feed.clone()),
+    );
+
+    let directory = std::env::temp_dir().join(format!("iotics-cli-import-{}", std::process::id()));
+    let directory_arg = directory.to_str().unwrap();
+
+    let opts = ExportArgs::from_iter([
+        "export",
+        "--config",
+        "test",
+        "--all",
+        "--format",
+        "yaml",
+        "--output",
+        directory_arg,
+    ]);
+    let mut stdout = Vec::new();
+    Export::with_host_api(&mut stdout, opts, source)
+        .run()
+        .await
+        .unwrap();
+
+    let opts = ImportArgs::from_iter(["import", "--config", "test", "--input", directory_arg]);
+    let mut stdout = Vec::new();
+    let result = Import::with_host_api(&mut stdout, opts, target.clone())
+        .run()
+        .await;
+    std::fs::remove_dir_all(&directory).unwrap();
+    result.unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("did:iotics:model   did:iotics:import-model   created"));
+    assert!(output.contains("Imported 2 twins, 0 failed."));
+
+    let twin = target.twin("did:iotics:import-twin-0").unwrap();
+    assert_eq!(twin.location, Some((51.5, -0.12)));
+    for key in [CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY] {
+        assert!(twin.properties.iter().any(|property| property.key == key));
+    }
+    assert_eq!(twin.feeds[0].id, "temperature");
+    assert!(twin.feeds[0].store_last);
+    assert_eq!(twin.feeds[0].properties, feed.properties);
+    assert_eq!(
+        find_twins_by_model(target, "did:iotics:import-model")
+            .await
+            .unwrap(),
+        vec!["did:iotics:import-twin-0"]
+    );
+}
+
+#[tokio::test]
+async fn clone_model_copies_the_last_values() {
+    Paint::disable();
+
+    let mock_network = MockNetwork::new();
+    let source = mock_network.add_host(LOCAL_HOST, "did:iotics:local");
+    let destination = mock_network.add_host("https://dev.iotics.test:10001", "did:iotics:dev");
+    let feed = MockFeed::new("temperature")
+        .store_last()
+        .with_label("Temperature")
+        .with_json(serde_json::json!({ "reading": 20.5 }))
+        .with_json(serde_json::json!({ "reading": 21.5 }));
+    source.add_twin(MockTwin::new(MODEL_DID));
+    source.add_twin(
+        MockTwin::new("did:iotics:twin-0")
+            .with_model(MODEL_DID)
+            .with_feed(feed.clone()),
+    );
+
+    let opts = CloneModelArgs::from_iter([
+        "clone-model",
+        "--config",
+        "customer",
+        "--to-config",
+        "dev",
+        "--model-did",
+        MODEL_DID,
+        "--with-last-values",
+        "--follower-twin-did",
+        "did:iotics:follower",
+    ]);
+
+    let mut stdout = Vec::new();
+    CloneModel::with_host_api(&mut stdout, opts, source, destination.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("Found 1 twins for model did:iotics:model. Cloning 2 twins"));
+    assert!(output.contains("Cloned 2 twins, 0 failed."));
+
+    assert_eq!(
+        destination.twins_dids(),
+        vec!["did:iotics:import-model", "did:iotics:import-twin-0"]
+    );
+    let twin = destination.twin("did:iotics:import-twin-0").unwrap();
+    assert_eq!(twin.feeds[0].properties, feed.properties);
+    assert_eq!(twin.feeds[0].data.len(), 1);
+    assert_eq!(twin.feeds[0].data[0].data, br#"{"reading":21.5}"#.to_vec());
+}
+
+#[tokio::test]
+async fn model_instantiate_creates_twins_inheriting_from_the_model() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(
+        MockTwin::new(MODEL_DID)
+            .with_label("Sensor")
+            .with_uri(TYPE_PROPERTY, "https://data.iotics.com/app#Model")
+            .with_uri(
+                "https://data.iotics.com/app#defines",
+                "http://schema.org/Thing",
+            )
+            .with_uri(
+                "https://data.iotics.com/app#color",
+                "http://example.com/red",
+            )
+            .with_feed(MockFeed::new("temperature").store_last()),
+    );
+
+    let opts = ModelInstantiateArgs::from_iter([
+        "instantiate",
+        "--config",
+        "test",
+        "--model-did",
+        MODEL_DID,
+        "--count",
+        "3",
+        "--property",
+        "http://schema.org/serialNumber=LT-{index}",
+    ]);
+    let mut stdout = Vec::new();
+    ModelInstantiate::with_host_api(&mut stdout, opts, host.clone())
+        .run()
+        .await
+        .unwrap();
+    assert!(String::from_utf8(stdout)
+        .unwrap()
+        .contains("Created 3 twins, 0 failed."));
+
+    let twin = host.twin("did:iotics:model-instance-2").unwrap();
+    assert!(twin.feeds[0].store_last);
+    for key in [CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY] {
+        assert!(twin.properties.iter().any(|property| property.key == key));
+    }
+
+    let opts =
+        ModelDescribeArgs::from_iter(["describe", "--config", "test", "--model-did", MODEL_DID]);
+    let mut stdout = Vec::new();
+    ModelDescribe::with_host_api(&mut stdout, opts, host.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("Inherited by instances (3)"));
+    assert!(output.contains("rdf:type          schema:Thing"));
+    assert!(output.contains("iotics-app:color  http://example.com/red"));
+    assert!(output.contains("3 twins created from the model."));
+
+    let opts = ModelListArgs::from_iter(["list", "--config", "test"]);
+    let mut stdout = Vec::new();
+    ModelList::with_host_api(&mut stdout, opts, host)
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("did:iotics:model  Sensor  3"));
+}
+
+#[tokio::test]
+async fn check_deletes_the_orphan_twins() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(MockTwin::new("did:iotics:twin-0").with_model(MODEL_DID));
+    host.add_twin(MockTwin::new("did:iotics:twin-1"));
+
+    let opts = CheckArgs::from_iter(["check", "--config", "test", "--plan", "--delete-orphans"]);
+
+    let mut stdout = Vec::new();
+    let result = Check::with_host_api(&mut stdout, opts, host.clone())
+        .run()
+        .await;
+    assert!(result.is_err());
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("orphan twin  did:iotics:twin-0  model did:iotics:model not found"));
+    assert!(output.contains("1. delete twin did:iotics:twin-0"));
+    assert!(output.contains("Would delete 1 orphan twins, run again with --yes to delete them:"));
+    assert_eq!(
+        host.twins_dids(),
+        vec!["did:iotics:twin-0", "did:iotics:twin-1"]
+    );
+
+    let opts = CheckArgs::from_iter(["check", "--config", "test", "--delete-orphans", "--yes"]);
+
+    let mut stdout = Vec::new();
+    Check::with_host_api(&mut stdout, opts, host.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("Deleting 1 orphan twins..."));
+    assert_eq!(host.twins_dids(), vec!["did:iotics:twin-1"]);
+}
+
+#[tokio::test]
+async fn gc_dry_run_does_not_delete() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(
+        MockTwin::new("did:iotics:twin-0").with_feed(MockFeed::new("temperature").store_last()),
+    );
+    host.add_twin(MockTwin::new("did:iotics:twin-1"));
+
+    let opts = GcArgs::from_iter([
+        "gc",
+        "--config",
+        "test",
+        "--inactive-days",
+        "7",
+        "--dry-run",
+    ]);
+
+    let mut stdout = Vec::new();
+    Gc::with_host_api(&mut stdout, opts, host.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(stdout).unwrap();
+    assert!(output.contains("did:iotics:twin-0                    never"));
+    assert!(output.contains("Found 1 stale twins out of 2."));
+    assert!(output.contains("Dry run, no twin deleted."));
+    assert_eq!(host.twins_dids().len(), 2);
+}
+
+#[test]
+fn replay_rejects_an_invalid_speed() {
+    let args = |speed: &str| {
+        ReplayArgs::from_iter_safe([
+            "replay",
+            "--config",
+            "test",
+            "--archive",
+            "samples.ndjson",
+            "--target-twin-did",
+            "did:iotics:twin-0",
+            "--speed",
+            speed,
+        ])
+    };
+
+    for speed in ["0", "-1", "NaN", "inf", "1e-300", "fast"] {
+        assert!(args(speed).is_err(), "speed {} accepted", speed);
+    }
+    assert_eq!(args("2.5").unwrap().speed, 2.5);
+}
+
+#[tokio::test]
+async fn describe_twins_fails_if_a_twin_fails_in_every_output_format() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(MockTwin::new("did:iotics:twin-0").with_label("Sensor"));
+
+    for output in ["table", "ndjson"] {
+        let opts = DescribeTwinsArgs::from_iter([
+            "describe-twins",
+            "--config",
+            "test",
+            "--twin-did",
+            "did:iotics:twin-0",
+            "--twin-did",
+            "did:iotics:missing",
+            "--output",
+            output,
+        ]);
+
+        let mut stdout = Vec::new();
+        let result = DescribeTwins::with_host_api(&mut stdout, opts, host.clone())
+            .run()
+            .await;
+
+        let e = result.expect_err(output);
+        assert_eq!(
+            e.to_string(),
+            "failed to describe 1 twins: did:iotics:missing"
+        );
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        match output {
+            "table" => assert!(stdout.contains("Described 1 twins, 1 failed.")),
+            // only the descriptions, the failure is logged
+            _ => assert_eq!(
+                stdout
+                    .lines()
+                    .map(|line| serde_json::from_str::<PortableTwin>(line).unwrap().twin_did)
+                    .collect::<Vec<String>>(),
+                vec!["did:iotics:twin-0"]
+            ),
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_capped_twins_count_stops_reading_the_search() {
+    let page_size = PAGE_SIZE as usize;
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    for index in 0..page_size * 5 {
+        host.add_twin(MockTwin::new(&format!("did:iotics:twin-{}", index)));
+    }
+
+    let twins_count = count_twins(host.as_ref(), Some(page_size + 1))
+        .await
+        .unwrap();
+
+    assert_eq!(twins_count.to_string(), format!(">{}", page_size + 1));
+    assert_eq!(host.pages_read(), 2);
+}
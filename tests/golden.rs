@@ -0,0 +1,188 @@
+//! Runs the commands against mock hosts and compares their output to the files in `tests/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to update the files after an intended change of the output.
+
+use std::collections::HashMap;
+use structopt::StructOpt;
+use yansi::Paint;
+
+use iotics_grpc_client::common::Visibility;
+
+use iotics_cli::commands::delete_all_twins::{DeleteAllTwins, DeleteAllTwinsArgs};
+use iotics_cli::commands::delete_twins_by_model::{DeleteTwinsByModel, DeleteTwinsByModelArgs};
+use iotics_cli::commands::describe_twin::{DescribeTwin, DescribeTwinArgs};
+use iotics_cli::commands::list_hosts::{ListHosts, ListHostsArgs};
+use iotics_cli::commands::RunnableCommand;
+use iotics_cli::operations::host_api::HostApi;
+use iotics_cli::operations::value_definitions::ValueDefinition;
+use iotics_cli::testing::{
+    self, assert_golden, DnsServer, IndexServer, MockFeed, MockNetwork, MockTwin,
+};
+
+const MODEL_DID: &str = "did:iotics:model";
+const LOCAL_HOST: &str = "https://local.iotics.test:10001";
+const TYPE_PROPERTY: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+fn value(label: &str, data_type: &str, unit: &str, comment: &str) -> ValueDefinition {
+    ValueDefinition {
+        label: label.to_string(),
+        data_type: data_type.to_string(),
+        unit: unit.to_string(),
+        comment: comment.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn list_hosts_table() {
+    Paint::disable();
+
+    let network = testing::network();
+    let local_index = IndexServer::start("4.0.1").await.unwrap();
+    let remote_index = IndexServer::start("3.2.0").await.unwrap();
+
+    let mock_network = MockNetwork::new();
+    let local = mock_network.add_host(&network.grpc_url(&local_index.host()), "did:iotics:local");
+    let remote =
+        mock_network.add_host(&network.grpc_url(&remote_index.host()), "did:iotics:remote");
+    let hidden = mock_network.add_host("https://hidden.iotics.test:10001", "did:iotics:hidden");
+
+    for (host, host_did) in [
+        (&local, "did:iotics:local"),
+        (&remote, "did:iotics:remote"),
+        (&hidden, "did:iotics:hidden"),
+    ] {
+        host.add_twin(
+            MockTwin::new(host_did)
+                .with_uri(TYPE_PROPERTY, "http://data.iotics.com/public#HostTwin")
+                .with_visibility(Visibility::Public),
+        );
+    }
+    local.add_twin(MockTwin::new("did:iotics:twin-0"));
+    local.add_twin(MockTwin::new("did:iotics:twin-1"));
+
+    let dns_server = DnsServer::start(HashMap::from([(
+        network.dns_name("did:iotics:remote"),
+        vec![remote_index.host()],
+    )]))
+    .await
+    .unwrap();
+    let nameserver = dns_server.address().to_string();
+
+    let opts = ListHostsArgs::from_iter([
+        "list-hosts",
+        "--config",
+        "test",
+        "--with-version",
+        "--with-twins",
+        "--count-cache-ttl",
+        "0",
+        "--sort-by",
+        "twins",
+        "--nameserver",
+        &nameserver,
+    ]);
+
+    let mut stdout = Vec::new();
+    let settings = testing::settings(&local.host_address());
+    ListHosts::with_host_api(&mut stdout, opts, settings, local.clone())
+        .run()
+        .await
+        .unwrap();
+
+    assert_golden("list_hosts_table", &String::from_utf8(stdout).unwrap());
+}
+
+#[tokio::test]
+async fn delete_twins_by_model() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(MockTwin::new(MODEL_DID));
+    for index in 0..3 {
+        host.add_twin(MockTwin::new(&format!("did:iotics:twin-{}", index)).with_model(MODEL_DID));
+    }
+    host.fail_deletion("did:iotics:twin-1");
+
+    let opts = DeleteTwinsByModelArgs::from_iter([
+        "delete-twins-by-model",
+        "--config",
+        "test",
+        "--model-did",
+        MODEL_DID,
+        "--delete-model",
+    ]);
+
+    let mut stdout = Vec::new();
+    DeleteTwinsByModel::with_host_api(&mut stdout, opts, host)
+        .run()
+        .await
+        .unwrap();
+
+    assert_golden("delete_twins_by_model", &String::from_utf8(stdout).unwrap());
+}
+
+#[tokio::test]
+async fn delete_all_twins() {
+    Paint::disable();
+
+    // more than a line of progress
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    for index in 0..70 {
+        host.add_twin(MockTwin::new(&format!("did:iotics:twin-{}", index)));
+    }
+    host.fail_deletion("did:iotics:twin-5");
+
+    let opts = DeleteAllTwinsArgs::from_iter(["delete-all-twins", "--config", "test"]);
+
+    let mut stdout = Vec::new();
+    DeleteAllTwins::with_host_api(&mut stdout, opts, host)
+        .run()
+        .await
+        .unwrap();
+
+    assert_golden("delete_all_twins", &String::from_utf8(stdout).unwrap());
+}
+
+#[tokio::test]
+async fn describe_twin() {
+    Paint::disable();
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(
+        MockTwin::new("did:iotics:twin-0")
+            .with_label("Weather station")
+            .with_model(MODEL_DID)
+            .with_uri(TYPE_PROPERTY, "http://schema.org/WeatherStation")
+            .with_visibility(Visibility::Public)
+            .with_location(51.5, -0.12)
+            .with_feed(
+                MockFeed::new("temperature")
+                    .store_last()
+                    .with_value(
+                        "reading",
+                        "decimal",
+                        "http://qudt.org/vocab/unit/DEG_C",
+                        "In celsius",
+                    )
+                    .with_value("status", "string", "", "Sensor status"),
+            )
+            .with_feed(MockFeed::new("heartbeat"))
+            .with_input("reset", vec![value("confirm", "boolean", "", "")]),
+    );
+
+    let opts = DescribeTwinArgs::from_iter([
+        "describe-twin",
+        "--config",
+        "test",
+        "--twin-did",
+        "did:iotics:twin-0",
+    ]);
+
+    let mut stdout = Vec::new();
+    DescribeTwin::with_host_api(&mut stdout, opts, host)
+        .run()
+        .await
+        .unwrap();
+
+    assert_golden("describe_twin", &String::from_utf8(stdout).unwrap());
+}
@@ -0,0 +1,489 @@
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use iotics_grpc_client::common::{Literal, Property, Uri, Value, Visibility};
+use iotics_grpc_client::twin::PAGE_SIZE;
+
+use iotics_cli::operations::delete::{delete_all_twins, delete_twins_by_model};
+use iotics_cli::operations::describe::fetch_twin_description;
+use iotics_cli::operations::follow::{follow_by_model, FollowByModelOptions, FollowEvent};
+use iotics_cli::operations::gc::{
+    find_stale_twins, Activity, StaleCriteria, CREATED_AT_PROPERTY, UPDATED_AT_PROPERTY,
+};
+use iotics_cli::operations::host_api::HostApi;
+use iotics_cli::operations::import::{import_twins, map_twins_dids, remap_twin};
+use iotics_cli::operations::integrity::{check_integrity, IntegrityIssue};
+use iotics_cli::operations::models::{instance_description, Instance, MODEL_TYPE};
+use iotics_cli::operations::twins::{find_twins_by_model, list_twins, TYPE_PROPERTY};
+use iotics_cli::testing::{MockFeed, MockHost, MockNetwork, MockTwin};
+
+const MODEL_DID: &str = "did:iotics:model";
+const LOCAL_HOST: &str = "https://local.iotics.test:10001";
+const REMOTE_HOST: &str = "https://remote.iotics.test:10001";
+
+fn twin_did(index: usize) -> String {
+    format!("did:iotics:twin-{}", index)
+}
+
+/// A host with the model, `count` twins created from it and an unrelated twin.
+fn host_with_model_twins(count: usize) -> Arc<MockHost> {
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(MockTwin::new(MODEL_DID));
+
+    for index in 0..count {
+        host.add_twin(MockTwin::new(&twin_did(index)).with_model(MODEL_DID));
+    }
+
+    host.add_twin(MockTwin::new("did:iotics:unrelated"));
+
+    host
+}
+
+// the time is paused, the search ending on the next page timeout doesn't slow the test down
+#[tokio::test(start_paused = true)]
+async fn find_twins_by_model_ends_after_the_last_page() {
+    let page_size = PAGE_SIZE as usize;
+
+    for count in [0, 1, page_size, page_size * 2 + 1] {
+        let host = host_with_model_twins(count);
+
+        let twins_dids = timeout(
+            Duration::from_secs(600),
+            find_twins_by_model(host, MODEL_DID),
+        )
+        .await
+        .expect("the search never ended")
+        .unwrap();
+
+        assert_eq!(
+            twins_dids,
+            (0..count).map(twin_did).collect::<Vec<String>>()
+        );
+    }
+}
+
+#[tokio::test]
+async fn delete_twins_by_model_reports_the_failed_deletions() {
+    let host = host_with_model_twins(3);
+    host.fail_deletion(&twin_did(1));
+
+    let report = delete_twins_by_model(host.clone(), MODEL_DID, true)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        report.deleted,
+        vec![twin_did(0), twin_did(2), MODEL_DID.to_string()]
+    );
+    assert_eq!(
+        report
+            .failed
+            .iter()
+            .map(|(twin_did, _)| twin_did.clone())
+            .collect::<Vec<String>>(),
+        vec![twin_did(1)]
+    );
+    assert_eq!(
+        host.twins_dids(),
+        vec![twin_did(1), "did:iotics:unrelated".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn delete_all_twins_only_deletes_the_local_twins() {
+    let network = MockNetwork::new();
+    let local = network.add_host(LOCAL_HOST, "did:iotics:local");
+    let remote = network.add_host(REMOTE_HOST, "did:iotics:remote");
+
+    for index in 0..3 {
+        local.add_twin(MockTwin::new(&twin_did(index)));
+    }
+    remote.add_twin(MockTwin::new("did:iotics:remote-twin"));
+
+    let report = delete_all_twins(local.clone()).await.unwrap();
+
+    assert_eq!(report.deleted.len(), 3);
+    assert!(report.failed.is_empty());
+    assert!(local.twins_dids().is_empty());
+    assert_eq!(remote.twins_dids(), vec!["did:iotics:remote-twin"]);
+}
+
+#[tokio::test]
+async fn list_twins_fails_when_the_host_is_unreachable() {
+    let host = host_with_model_twins(1);
+    host.set_offline(true);
+
+    assert!(list_twins(host).await.is_err());
+}
+
+#[tokio::test]
+async fn follow_by_model_follows_the_twins_of_every_host() {
+    let network = MockNetwork::new();
+    let local = network.add_host(LOCAL_HOST, "did:iotics:local");
+    let remote = network.add_host(REMOTE_HOST, "did:iotics:remote");
+
+    local.add_twin(MockTwin::new("did:iotics:follower"));
+    local.add_twin(
+        MockTwin::new("did:iotics:local-twin")
+            .with_model(MODEL_DID)
+            .with_feed(
+                MockFeed::new("temperature")
+                    .with_json(serde_json::json!({ "value": 20.5 }))
+                    .with_json(serde_json::json!({ "value": 21.0 })),
+            ),
+    );
+    // the remote twin doesn't have the feed, the private one found first would take its place if it was shared
+    remote.add_twin(MockTwin::new("did:iotics:private-twin").with_model(MODEL_DID));
+    remote.add_twin(
+        MockTwin::new("did:iotics:remote-twin")
+            .with_model(MODEL_DID)
+            .with_visibility(Visibility::Public),
+    );
+
+    let events = follow_by_model(
+        local,
+        FollowByModelOptions {
+            model_did: MODEL_DID.to_string(),
+            feed_id: "temperature".to_string(),
+            follower_twin_did: "did:iotics:follower".to_string(),
+            maximum_twins: 2,
+            describe_feed: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    let events = timeout(
+        Duration::from_secs(10),
+        events.collect::<Vec<FollowEvent>>(),
+    )
+    .await
+    .expect("the follow never ended");
+
+    let data = events
+        .iter()
+        .filter_map(|event| match event {
+            FollowEvent::Data { twin_did, .. } => Some(twin_did.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<&str>>();
+    assert_eq!(data, vec!["did:iotics:local-twin", "did:iotics:local-twin"]);
+
+    // the feed is described before its first data
+    let described = events.iter().position(|event| {
+        matches!(event, FollowEvent::Described { twin_did, values: Ok(_), .. } if twin_did == "did:iotics:local-twin")
+    });
+    let first_data = events
+        .iter()
+        .position(|event| matches!(event, FollowEvent::Data { .. }));
+    assert!(described.unwrap() < first_data.unwrap());
+
+    let mut failed = events
+        .iter()
+        .filter_map(|event| match event {
+            FollowEvent::Failed { twin_did, .. } => Some(twin_did.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<&str>>();
+    failed.sort_unstable();
+    assert_eq!(
+        failed,
+        vec!["did:iotics:local-twin", "did:iotics:remote-twin"]
+    );
+}
+
+#[tokio::test]
+async fn import_restores_the_deleted_twins_of_the_agent() {
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_own_twin(MockTwin::new("did:iotics:own"));
+    host.add_twin(MockTwin::new("did:iotics:foreign"));
+
+    let mut descriptions = Vec::new();
+    for twin_did in ["did:iotics:own", "did:iotics:foreign"] {
+        descriptions.push(
+            fetch_twin_description(host.clone(), twin_did, None)
+                .await
+                .unwrap(),
+        );
+        host.delete_twin(twin_did).await.unwrap();
+    }
+
+    let twins_dids = descriptions
+        .iter()
+        .map(|description| description.twin_did.clone())
+        .collect::<Vec<String>>();
+    let mappings = map_twins_dids(host.clone(), &twins_dids, true)
+        .await
+        .unwrap();
+    let twins_dids = mappings
+        .iter()
+        .map(|mapping| (mapping.twin_did.as_str(), mapping.created))
+        .collect::<Vec<(&str, bool)>>();
+    // the twin of another agent can't be restored with its DID
+    assert_eq!(
+        twins_dids,
+        vec![
+            ("did:iotics:own", false),
+            ("did:iotics:import-foreign", true),
+        ]
+    );
+
+    let descriptions = descriptions
+        .into_iter()
+        .map(|description| remap_twin(description, &mappings))
+        .collect();
+    let results = import_twins(host.clone(), descriptions)
+        .collect::<Vec<(String, Result<(), anyhow::Error>)>>()
+        .await;
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+    assert_eq!(
+        host.twins_dids(),
+        vec!["did:iotics:own", "did:iotics:import-foreign"]
+    );
+}
+
+#[tokio::test]
+async fn import_remaps_the_references_to_the_new_twins() {
+    let source = host_with_model_twins(2);
+    let target = MockNetwork::new().add_host(REMOTE_HOST, "did:iotics:remote");
+    // already owned by the agent on the target host
+    target.add_own_twin(MockTwin::new(&twin_did(1)));
+
+    let twins_dids = vec![MODEL_DID.to_string(), twin_did(0), twin_did(1)];
+    let mut descriptions = Vec::new();
+    for twin_did in &twins_dids {
+        descriptions.push(
+            fetch_twin_description(source.clone(), twin_did, None)
+                .await
+                .unwrap(),
+        );
+    }
+
+    let mappings = map_twins_dids(target.clone(), &twins_dids, true)
+        .await
+        .unwrap();
+    let twins_dids = mappings
+        .iter()
+        .map(|mapping| (mapping.twin_did.as_str(), mapping.created))
+        .collect::<Vec<(&str, bool)>>();
+    assert_eq!(
+        twins_dids,
+        vec![
+            ("did:iotics:import-model", true),
+            ("did:iotics:import-twin-0", true),
+            ("did:iotics:twin-1", false),
+        ]
+    );
+
+    let descriptions = descriptions
+        .into_iter()
+        .map(|description| remap_twin(description, &mappings))
+        .collect();
+    let results = import_twins(target.clone(), descriptions)
+        .collect::<Vec<(String, Result<(), anyhow::Error>)>>()
+        .await;
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+    let model_dids = find_twins_by_model(target.clone(), "did:iotics:import-model")
+        .await
+        .unwrap();
+    assert_eq!(
+        model_dids,
+        vec!["did:iotics:import-twin-0", "did:iotics:twin-1"]
+    );
+}
+
+#[tokio::test]
+async fn check_integrity_reports_every_kind_of_issue() {
+    const COLOR_PROPERTY: &str = "https://data.iotics.com/app#color";
+
+    let network = MockNetwork::new();
+    let host = network.add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(
+        MockTwin::new(MODEL_DID)
+            .with_label("Sensor")
+            .with_uri(TYPE_PROPERTY, MODEL_TYPE)
+            .with_uri(COLOR_PROPERTY, "http://example.com/red")
+            .with_feed(MockFeed::new("temperature")),
+    );
+    host.add_twin(MockTwin::new("did:iotics:unused").with_uri(TYPE_PROPERTY, MODEL_TYPE));
+    host.add_twin(
+        MockTwin::new(&twin_did(0))
+            .with_label("Sensor 0")
+            .with_model(MODEL_DID)
+            .with_uri(COLOR_PROPERTY, "http://example.com/blue")
+            .with_feed(MockFeed::new("temperature")),
+    );
+    host.add_twin(
+        MockTwin::new(&twin_did(1))
+            .with_label("Sensor 0")
+            .with_model(MODEL_DID),
+    );
+    host.add_twin(MockTwin::new("did:iotics:orphan").with_model("did:iotics:gone"));
+    // neither is an orphan: one model is on the host without the model type, the other on another host
+    host.add_twin(MockTwin::new("did:iotics:untyped-model"));
+    host.add_twin(MockTwin::new(&twin_did(2)).with_model("did:iotics:untyped-model"));
+    network.add_host(REMOTE_HOST, "did:iotics:remote").add_twin(
+        MockTwin::new("did:iotics:remote-model")
+            .with_uri(TYPE_PROPERTY, MODEL_TYPE)
+            .with_visibility(Visibility::Public),
+    );
+    host.add_twin(MockTwin::new(&twin_did(3)).with_model("did:iotics:remote-model"));
+
+    let issues = check_integrity(host, 4).await.unwrap();
+
+    assert_eq!(
+        issues,
+        vec![
+            IntegrityIssue::OrphanTwin {
+                twin_did: "did:iotics:orphan".to_string(),
+                model_did: "did:iotics:gone".to_string(),
+            },
+            IntegrityIssue::UnusedModel {
+                model_did: "did:iotics:unused".to_string(),
+            },
+            IntegrityIssue::MissingProperty {
+                twin_did: twin_did(1),
+                model_did: MODEL_DID.to_string(),
+                key: COLOR_PROPERTY.to_string(),
+            },
+            IntegrityIssue::MissingFeed {
+                twin_did: twin_did(1),
+                model_did: MODEL_DID.to_string(),
+                feed_id: "temperature".to_string(),
+            },
+            IntegrityIssue::DuplicateLabel {
+                label: "Sensor 0".to_string(),
+                twins_dids: vec![twin_did(0), twin_did(1)],
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn instance_properties_replace_the_inherited_ones() {
+    const COLOR_PROPERTY: &str = "https://data.iotics.com/app#color";
+    const SIZE_PROPERTY: &str = "https://data.iotics.com/app#size";
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(
+        MockTwin::new(MODEL_DID)
+            .with_uri(COLOR_PROPERTY, "http://example.com/red")
+            .with_uri(SIZE_PROPERTY, "http://example.com/small"),
+    );
+    let model = fetch_twin_description(host, MODEL_DID, None).await.unwrap();
+
+    let blue = Value::UriValue(Uri {
+        value: "http://example.com/blue".to_string(),
+    });
+    let instance = Instance {
+        twin_key_name: "instance".to_string(),
+        label: "Blue sensor".to_string(),
+        properties: vec![Property {
+            key: COLOR_PROPERTY.to_string(),
+            value: Some(blue.clone()),
+        }],
+    };
+
+    let description = instance_description(&model, "did:iotics:instance", &instance);
+
+    let values = |key: &str| {
+        description
+            .properties
+            .iter()
+            .filter(|property| property.key == key)
+            .map(|property| property.value.clone())
+            .collect::<Vec<Option<Value>>>()
+    };
+    assert_eq!(values(COLOR_PROPERTY), vec![Some(blue)]);
+    assert_eq!(values(SIZE_PROPERTY).len(), 1);
+}
+
+fn created_at(date_time: &str) -> Value {
+    Value::LiteralValue(Literal {
+        value: date_time.to_string(),
+        data_type: "dateTime".to_string(),
+    })
+}
+
+#[tokio::test]
+async fn find_stale_twins_keeps_the_twins_that_do_not_tell() {
+    // 2022-01-01 and 2022-03-01, in seconds
+    const JANUARY: i64 = 1640995200;
+    const MARCH: i64 = 1646092800;
+
+    let host = MockNetwork::new().add_host(LOCAL_HOST, "did:iotics:local");
+    host.add_twin(
+        MockTwin::new(&twin_did(0))
+            .with_property(CREATED_AT_PROPERTY, created_at("2021-12-01T10:00:00Z"))
+            .with_feed(
+                MockFeed::new("temperature")
+                    .store_last()
+                    .with_json_at(serde_json::json!(20), JANUARY),
+            ),
+    );
+    // shared recently
+    host.add_twin(
+        MockTwin::new(&twin_did(1))
+            .with_property(CREATED_AT_PROPERTY, created_at("2021-12-01T10:00:00Z"))
+            .with_feed(
+                MockFeed::new("temperature")
+                    .store_last()
+                    .with_json_at(serde_json::json!(20), MARCH),
+            ),
+    );
+    // never shared
+    host.add_twin(
+        MockTwin::new(&twin_did(2))
+            .with_property(CREATED_AT_PROPERTY, created_at("2021-12-01T10:00:00Z"))
+            .with_feed(MockFeed::new("temperature").store_last()),
+    );
+    // no feed storing its last value
+    host.add_twin(
+        MockTwin::new(&twin_did(3))
+            .with_property(CREATED_AT_PROPERTY, created_at("2021-12-01T10:00:00Z"))
+            .with_feed(MockFeed::new("temperature")),
+    );
+    // no creation date
+    host.add_twin(MockTwin::new(&twin_did(4)).with_feed(MockFeed::new("temperature").store_last()));
+    // never shared, but updated recently
+    host.add_twin(
+        MockTwin::new(&twin_did(5))
+            .with_property(CREATED_AT_PROPERTY, created_at("2021-12-01T10:00:00Z"))
+            .with_property(UPDATED_AT_PROPERTY, created_at("2022-02-15T10:00:00Z"))
+            .with_feed(MockFeed::new("temperature").store_last()),
+    );
+
+    let date = |value: &str| {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&chrono::Utc)
+    };
+    let criteria = StaleCriteria {
+        created_before: Some(date("2022-01-01T00:00:00Z")),
+        updated_before: None,
+        inactive_since: Some(date("2022-02-01T00:00:00Z")),
+    };
+
+    let stale_twins = find_stale_twins(host, &criteria, 2).await.unwrap();
+
+    assert_eq!(stale_twins.checked, 6);
+    assert!(stale_twins.failed.is_empty());
+    let stale = stale_twins
+        .stale
+        .iter()
+        .map(|age| (age.twin_did.clone(), age.activity))
+        .collect::<Vec<(String, Activity)>>();
+    assert_eq!(
+        stale,
+        vec![
+            (
+                twin_did(0),
+                Activity::LastShared(date("2022-01-01T00:00:00Z"))
+            ),
+            (twin_did(2), Activity::NeverShared),
+        ]
+    );
+}